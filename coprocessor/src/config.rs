@@ -0,0 +1,71 @@
+use crate::error::{CoprocessorError, Result};
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+const DEFAULT_WS_URL: &str = "ws://127.0.0.1:8900";
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 10;
+const DEFAULT_CIPHERTEXT_DIR: &str = "ciphertexts";
+
+/// Runtime configuration of the coprocessor.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub rpc_url: String,
+    pub ws_url: String,
+    /// Keypair registered as `StateAccount::coprocessor`; pays for result accounts.
+    pub keypair_path: PathBuf,
+    /// Bincode-serialized network `ServerKey`.
+    pub server_key_path: PathBuf,
+    pub poll_interval: Duration,
+    /// Ciphertext store: uploaded trait payloads are read from it and score
+    /// ciphertexts written to it.
+    pub ciphertext_dir: PathBuf,
+    /// URI the ciphertext directory is published under.
+    pub ciphertext_base_uri: String,
+}
+
+impl Config {
+    /// Reads the configuration from `SOLFHE_*` environment variables.
+    ///
+    /// `SOLFHE_SERVER_KEY` is required; everything else has a localnet default.
+    /// Unless `SOLFHE_CIPHERTEXT_BASE_URI` is set, ciphertexts are published
+    /// as `file://` URIs of `SOLFHE_CIPHERTEXT_DIR`.
+    pub fn from_env() -> Result<Self> {
+        let server_key_path = env::var("SOLFHE_SERVER_KEY")
+            .map(PathBuf::from)
+            .map_err(|_| CoprocessorError::Config("SOLFHE_SERVER_KEY is not set".into()))?;
+
+        let keypair_path = match env::var("SOLFHE_KEYPAIR") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => {
+                let home = env::var("HOME")
+                    .map_err(|_| CoprocessorError::Config("HOME is not set".into()))?;
+                PathBuf::from(home).join(".config/solana/id.json")
+            }
+        };
+
+        let poll_interval = match env::var("SOLFHE_POLL_INTERVAL_SECS") {
+            Ok(secs) => secs.parse().map_err(|_| {
+                CoprocessorError::Config(format!("invalid SOLFHE_POLL_INTERVAL_SECS `{secs}`"))
+            })?,
+            Err(_) => DEFAULT_POLL_INTERVAL_SECS,
+        };
+
+        let ciphertext_dir = env::current_dir()?.join(
+            env::var("SOLFHE_CIPHERTEXT_DIR").unwrap_or_else(|_| DEFAULT_CIPHERTEXT_DIR.into()),
+        );
+        let ciphertext_base_uri = env::var("SOLFHE_CIPHERTEXT_BASE_URI")
+            .unwrap_or_else(|_| format!("file://{}", ciphertext_dir.display()));
+
+        Ok(Self {
+            rpc_url: env::var("SOLFHE_RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.into()),
+            ws_url: env::var("SOLFHE_WS_URL").unwrap_or_else(|_| DEFAULT_WS_URL.into()),
+            keypair_path,
+            server_key_path,
+            poll_interval: Duration::from_secs(poll_interval),
+            ciphertext_dir,
+            ciphertext_base_uri,
+        })
+    }
+}
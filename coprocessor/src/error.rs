@@ -0,0 +1,39 @@
+use solfhe::FheScheme;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, CoprocessorError>;
+
+#[derive(Debug, Error)]
+pub enum CoprocessorError {
+    #[error("configuration error: {0}")]
+    Config(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("ciphertext serialization error: {0}")]
+    Serialization(#[from] bincode::Error),
+    /// Boxed, as it dwarfs every other variant.
+    #[error("RPC client error: {0}")]
+    Client(Box<anchor_client::ClientError>),
+    #[error("loaded server key does not match the registry for epoch {epoch}")]
+    ServerKeyMismatch { epoch: u64 },
+    #[error("malformed ciphertext envelope: {0}")]
+    InvalidEnvelope(std::io::Error),
+    #[error("ciphertext payload does not match its envelope: {0}")]
+    InvalidPayload(anchor_client::anchor_lang::error::Error),
+    #[error("ciphertext URI `{0}` is not in the ciphertext store")]
+    UnknownCiphertextUri(String),
+    #[error("unsupported FHE scheme {0:?}")]
+    UnsupportedScheme(FheScheme),
+    #[error("profile uses trait schema {user} but the ad uses {target}")]
+    TraitSchemaMismatch { user: u16, target: u16 },
+    #[error("profile has {user} traits but the ad targets {target}")]
+    TraitCountMismatch { user: usize, target: usize },
+    #[error("{count} traits exceed the {max} the score ciphertext can hold")]
+    TooManyTraits { count: usize, max: usize },
+}
+
+impl From<anchor_client::ClientError> for CoprocessorError {
+    fn from(error: anchor_client::ClientError) -> Self {
+        CoprocessorError::Client(Box::new(error))
+    }
+}
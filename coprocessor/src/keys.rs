@@ -0,0 +1,67 @@
+//! Network FHE keys.
+//!
+//! The network client key is generated once by the key holder and distributed
+//! to clients; the coprocessor only ever loads the matching server key. The
+//! SHA-256 of each serialized key is what gets registered in the program's
+//! `FheKeyRegistry`.
+
+use crate::error::Result;
+use anchor_client::solana_sdk::hash::hash;
+use std::fs;
+use std::path::Path;
+use tfhe::shortint::parameters::PARAM_MESSAGE_2_CARRY_2;
+use tfhe::shortint::prelude::*;
+
+/// `FheKeyRegistry::parameter_set` identifier for `PARAM_MESSAGE_2_CARRY_2`.
+pub const PARAM_MESSAGE_2_CARRY_2_ID: u16 = 1;
+
+/// Hash of serialized key material, as stored in `FheKeyRegistry`.
+pub fn key_hash(serialized_key: &[u8]) -> [u8; 32] {
+    hash(serialized_key).to_bytes()
+}
+
+/// Lowercase hex encoding, as key hashes are printed and blobs are named.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Loads the persisted server key used for every homomorphic evaluation,
+/// along with its registry hash.
+pub fn load_server_key(path: &Path) -> Result<(ServerKey, [u8; 32])> {
+    let bytes = fs::read(path)?;
+    Ok((bincode::deserialize(&bytes)?, key_hash(&bytes)))
+}
+
+/// Hashes of a freshly generated network key set.
+pub struct GeneratedKeys {
+    pub public_key_hash: [u8; 32],
+    pub server_key_hash: [u8; 32],
+    pub parameter_set: u16,
+}
+
+/// Generates a fresh network key set and writes it to `out_dir` as
+/// `client_key.bin`, `public_key.bin` and `server_key.bin`.
+///
+/// The client key must never be deployed next to the coprocessor.
+pub fn generate_keys(out_dir: &Path) -> Result<GeneratedKeys> {
+    let client_key = ClientKey::new(PARAM_MESSAGE_2_CARRY_2);
+    let public_key = PublicKeyBig::new(&client_key);
+    let server_key = ServerKey::new(&client_key);
+
+    let public_key_bytes = bincode::serialize(&public_key)?;
+    let server_key_bytes = bincode::serialize(&server_key)?;
+
+    fs::create_dir_all(out_dir)?;
+    fs::write(
+        out_dir.join("client_key.bin"),
+        bincode::serialize(&client_key)?,
+    )?;
+    fs::write(out_dir.join("public_key.bin"), &public_key_bytes)?;
+    fs::write(out_dir.join("server_key.bin"), &server_key_bytes)?;
+
+    Ok(GeneratedKeys {
+        public_key_hash: key_hash(&public_key_bytes),
+        server_key_hash: key_hash(&server_key_bytes),
+        parameter_set: PARAM_MESSAGE_2_CARRY_2_ID,
+    })
+}
@@ -0,0 +1,38 @@
+//! # solFHE coprocessor
+//!
+//! The solFHE program only records ciphertexts: evaluating TFHE circuits needs
+//! programmable bootstrapping, which is far beyond the compute budget of a
+//! Solana instruction. The coprocessor runs that evaluation off-chain.
+//!
+//! It polls pending `MatchRequest`s, fetches the ciphertexts the requesting
+//! user's `UserProfile` and each candidate `AdAccount` point to from its
+//! ciphertext store, and computes an encrypted match score of the profile
+//! against each ad with the persisted network server key. It publishes the
+//! score ciphertexts to the store and posts their hashes back through
+//! `fulfil_match`.
+//!
+//! The coprocessor never holds a client key, so it cannot read profiles,
+//! targeting traits or the scores it produces.
+//!
+//! ## Module Structure
+//!
+//! - `config`: Runtime configuration read from the environment.
+//! - `error`: Error type shared by the coprocessor.
+//! - `keys`: Loading and generating the network FHE keys.
+//! - `matcher`: Homomorphic scoring of a profile against an ad.
+//! - `store`: Content-addressed storage for ciphertexts too large for a
+//!   transaction.
+//! - `watcher`: Polls match requests and fulfils them.
+
+pub mod config;
+pub mod error;
+pub mod keys;
+pub mod matcher;
+pub mod store;
+pub mod watcher;
+
+pub use config::Config;
+pub use error::{CoprocessorError, Result};
+pub use matcher::Matcher;
+pub use store::CiphertextStore;
+pub use watcher::{ProgramClient, RpcClient, Watcher};
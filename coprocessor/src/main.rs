@@ -0,0 +1,44 @@
+use solfhe_coprocessor::{keys, Config, CoprocessorError, Matcher, Result, Watcher};
+use std::path::Path;
+
+const USAGE: &str = "usage: solfhe-coprocessor [run | keygen <out-dir>]";
+
+fn main() {
+    env_logger::init();
+
+    if let Err(err) = run() {
+        log::error!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] | ["run"] => {
+            let config = Config::from_env()?;
+            let (server_key, server_key_hash) = keys::load_server_key(&config.server_key_path)?;
+            let mut watcher = Watcher::new(&config, Matcher::new(server_key), server_key_hash)?;
+
+            log::info!("solFHE coprocessor watching {}", config.rpc_url);
+            watcher.run()
+        }
+        ["keygen", out_dir] => {
+            let generated = keys::generate_keys(Path::new(out_dir))?;
+            println!("public_key_hash: {}", keys::hex(&generated.public_key_hash));
+            println!("server_key_hash: {}", keys::hex(&generated.server_key_hash));
+            println!("parameter_set:   {}", generated.parameter_set);
+            log::info!(
+                "network keys written to {out_dir}; keep client_key.bin away from the coprocessor"
+            );
+            Ok(())
+        }
+        _ => Err(CoprocessorError::Config(USAGE.into())),
+    }
+}
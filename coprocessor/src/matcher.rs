@@ -0,0 +1,193 @@
+use crate::error::{CoprocessorError, Result};
+use crate::store::CiphertextStore;
+use anchor_client::anchor_lang::AnchorDeserialize;
+use solfhe::{CiphertextEnvelope, FheScheme};
+use tfhe::shortint::prelude::*;
+
+/// Scores encrypted profiles against encrypted ad targeting traits.
+///
+/// The score of a pair is the number of traits where the profile equals the
+/// ad's target, computed entirely under encryption. It is returned as a single
+/// ciphertext that the user decrypts with `ClientKey::decrypt_message_and_carry`,
+/// since the sum may spill into the carry bits.
+pub struct Matcher {
+    server_key: ServerKey,
+}
+
+impl Matcher {
+    pub fn new(server_key: ServerKey) -> Self {
+        Self { server_key }
+    }
+
+    /// Largest trait count whose score still fits in message and carry bits.
+    pub fn max_traits(&self) -> usize {
+        self.server_key.message_modulus.0 * self.server_key.carry_modulus.0 - 1
+    }
+
+    /// Scores a profile against an ad's targeting traits and returns the
+    /// serialized encrypted score. At several kilobytes it is published
+    /// off-chain rather than sent to the program.
+    pub fn score(&self, profile: &EncryptedTraits, targets: &EncryptedTraits) -> Result<Vec<u8>> {
+        if profile.trait_schema != targets.trait_schema {
+            return Err(CoprocessorError::TraitSchemaMismatch {
+                user: profile.trait_schema,
+                target: targets.trait_schema,
+            });
+        }
+
+        // Smart operations clean carries in place, so work on copies
+        let mut user_traits = profile.ciphertexts.clone();
+        let mut target_traits = targets.ciphertexts.clone();
+
+        let score = self.score_ciphertexts(&mut user_traits, &mut target_traits)?;
+        Ok(bincode::serialize(&score)?)
+    }
+
+    fn score_ciphertexts(
+        &self,
+        user_traits: &mut [CiphertextBig],
+        target_traits: &mut [CiphertextBig],
+    ) -> Result<CiphertextBig> {
+        if user_traits.is_empty() || user_traits.len() != target_traits.len() {
+            return Err(CoprocessorError::TraitCountMismatch {
+                user: user_traits.len(),
+                target: target_traits.len(),
+            });
+        }
+        if user_traits.len() > self.max_traits() {
+            return Err(CoprocessorError::TooManyTraits {
+                count: user_traits.len(),
+                max: self.max_traits(),
+            });
+        }
+
+        // Each equality is an encrypted 0/1; the sum is bounded by max_traits,
+        // so unchecked additions cannot overflow the carry space.
+        let mut score = self.server_key.create_trivial(0);
+        for (user_trait, target_trait) in user_traits.iter_mut().zip(target_traits.iter_mut()) {
+            let equal = self.server_key.smart_equal(user_trait, target_trait);
+            self.server_key.unchecked_add_assign(&mut score, &equal);
+        }
+        Ok(score)
+    }
+}
+
+/// Ciphertexts referenced by a stored envelope.
+pub struct EncryptedTraits {
+    pub trait_schema: u16,
+    pub ciphertexts: Vec<CiphertextBig>,
+}
+
+impl EncryptedTraits {
+    /// Decodes an envelope stored by the program and fetches its payload. The
+    /// program already checked the key epoch; the payload is checked against
+    /// the envelope's length and checksum here.
+    pub fn load(envelope: &[u8], store: &CiphertextStore) -> Result<Self> {
+        let envelope = CiphertextEnvelope::try_from_slice(envelope)
+            .map_err(CoprocessorError::InvalidEnvelope)?;
+        if envelope.scheme != FheScheme::TfheShortint {
+            return Err(CoprocessorError::UnsupportedScheme(envelope.scheme));
+        }
+
+        let payload = store.get(&envelope.payload_uri)?;
+        envelope
+            .verify_payload(&payload)
+            .map_err(CoprocessorError::InvalidPayload)?;
+        Ok(Self {
+            trait_schema: envelope.trait_schema,
+            ciphertexts: bincode::deserialize(&payload)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_client::anchor_lang::AnchorSerialize;
+    use solfhe::TRAIT_SCHEMA_V1;
+    use tfhe::shortint::parameters::PARAM_MESSAGE_2_CARRY_2;
+
+    fn encrypt_all(client_key: &ClientKey, values: &[u64]) -> Vec<CiphertextBig> {
+        values.iter().map(|&v| client_key.encrypt(v)).collect()
+    }
+
+    #[test]
+    fn test_score_counts_matching_traits() {
+        let client_key = ClientKey::new(PARAM_MESSAGE_2_CARRY_2);
+        let matcher = Matcher::new(ServerKey::new(&client_key));
+
+        let mut user = encrypt_all(&client_key, &[1, 2, 3, 0, 1]);
+        let mut target = encrypt_all(&client_key, &[1, 2, 0, 0, 3]);
+
+        let score = matcher.score_ciphertexts(&mut user, &mut target).unwrap();
+        assert_eq!(client_key.decrypt_message_and_carry(&score), 3);
+    }
+
+    fn store() -> CiphertextStore {
+        let dir = std::env::temp_dir().join(format!("solfhe-matcher-{}", std::process::id()));
+        CiphertextStore::new(dir, "https://ciphertexts.solfhe.xyz".into()).unwrap()
+    }
+
+    /// Uploads encrypted `values` and returns the envelope the program stores.
+    fn envelope(store: &CiphertextStore, client_key: &ClientKey, values: &[u64]) -> Vec<u8> {
+        let payload = bincode::serialize(&encrypt_all(client_key, values)).unwrap();
+        let payload_uri = store.put(&payload).unwrap();
+        CiphertextEnvelope::new(
+            FheScheme::TfheShortint,
+            1,
+            0,
+            TRAIT_SCHEMA_V1,
+            payload_uri,
+            &payload,
+        )
+        .try_to_vec()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_score_real_ciphertexts() {
+        let client_key = ClientKey::new(PARAM_MESSAGE_2_CARRY_2);
+        let matcher = Matcher::new(ServerKey::new(&client_key));
+        let store = store();
+
+        let profile = envelope(&store, &client_key, &[1, 2, 3, 0, 1]);
+        let targets = envelope(&store, &client_key, &[1, 0, 3, 0, 2]);
+        let profile = EncryptedTraits::load(&profile, &store).unwrap();
+        let targets = EncryptedTraits::load(&targets, &store).unwrap();
+
+        let serialized = matcher.score(&profile, &targets).unwrap();
+        let score: CiphertextBig = bincode::deserialize(&serialized).unwrap();
+        assert_eq!(client_key.decrypt_message_and_carry(&score), 3);
+    }
+
+    #[test]
+    fn test_load_rejects_tampered_payload() {
+        let client_key = ClientKey::new(PARAM_MESSAGE_2_CARRY_2);
+        let store = store();
+
+        let mut envelope =
+            CiphertextEnvelope::try_from_slice(&envelope(&store, &client_key, &[1, 2, 3, 0, 1]))
+                .unwrap();
+        let other = bincode::serialize(&encrypt_all(&client_key, &[0, 0, 0, 0, 0])).unwrap();
+        envelope.payload_uri = store.put(&other).unwrap();
+
+        assert!(matches!(
+            EncryptedTraits::load(&envelope.try_to_vec().unwrap(), &store),
+            Err(CoprocessorError::InvalidPayload(_))
+        ));
+    }
+
+    #[test]
+    fn test_score_rejects_mismatched_trait_counts() {
+        let client_key = ClientKey::new(PARAM_MESSAGE_2_CARRY_2);
+        let matcher = Matcher::new(ServerKey::new(&client_key));
+
+        let mut user = encrypt_all(&client_key, &[1, 2]);
+        let mut target = encrypt_all(&client_key, &[1, 2, 3]);
+
+        assert!(matches!(
+            matcher.score_ciphertexts(&mut user, &mut target),
+            Err(CoprocessorError::TraitCountMismatch { user: 2, target: 3 })
+        ));
+    }
+}
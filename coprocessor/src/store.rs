@@ -0,0 +1,103 @@
+//! Off-chain ciphertext storage.
+//!
+//! FHE ciphertexts are kilobytes each, far more than a Solana transaction
+//! carries, so the program only records their hashes and URIs. The blobs live
+//! in a content-addressed directory that is published under a base URI, e.g.
+//! by a static file server. Clients upload their profile and targeting
+//! payloads to the same place.
+
+use crate::error::{CoprocessorError, Result};
+use crate::keys::hex;
+use anchor_client::solana_sdk::hash::hash;
+use solfhe::MAX_CIPHERTEXT_URI_LENGTH;
+use std::fs;
+use std::path::PathBuf;
+
+/// Content-addressed ciphertext blobs in a local directory, each published at
+/// `<base_uri>/<hex SHA-256 of the blob>`.
+pub struct CiphertextStore {
+    dir: PathBuf,
+    base_uri: String,
+}
+
+impl CiphertextStore {
+    pub fn new(dir: PathBuf, base_uri: String) -> Result<Self> {
+        let base_uri = base_uri.trim_end_matches('/').to_string();
+        // Base, separator and 64 hex digits must fit the program's URI bound
+        if base_uri.is_empty() || base_uri.len() + 65 > MAX_CIPHERTEXT_URI_LENGTH {
+            return Err(CoprocessorError::Config(format!(
+                "ciphertext base URI `{base_uri}` must be 1 to {} characters",
+                MAX_CIPHERTEXT_URI_LENGTH - 65
+            )));
+        }
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, base_uri })
+    }
+
+    /// Stores `blob` and returns the URI it is published at.
+    pub fn put(&self, blob: &[u8]) -> Result<String> {
+        let name = hex(&hash(blob).to_bytes());
+        fs::write(self.dir.join(&name), blob)?;
+        Ok(format!("{}/{name}", self.base_uri))
+    }
+
+    /// Reads the blob published at `uri`. Callers check it against the hash
+    /// the program recorded.
+    pub fn get(&self, uri: &str) -> Result<Vec<u8>> {
+        let name = uri
+            .strip_prefix(&self.base_uri)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .filter(|name| name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| CoprocessorError::UnknownCiphertextUri(uri.to_string()))?;
+        Ok(fs::read(self.dir.join(name))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn store(name: &str) -> CiphertextStore {
+        let dir = env::temp_dir().join(format!("solfhe-store-{name}-{}", std::process::id()));
+        CiphertextStore::new(dir, "https://ciphertexts.solfhe.xyz/".into()).unwrap()
+    }
+
+    #[test]
+    fn test_put_is_content_addressed() {
+        let store = store("put");
+        let uri = store.put(b"score").unwrap();
+        let name = hex(&hash(b"score").to_bytes());
+        assert_eq!(uri, format!("https://ciphertexts.solfhe.xyz/{name}"));
+        assert!(uri.len() <= MAX_CIPHERTEXT_URI_LENGTH);
+        assert_eq!(fs::read(store.dir.join(name)).unwrap(), b"score");
+    }
+
+    #[test]
+    fn test_get_reads_published_blobs_only() {
+        let store = store("get");
+        let uri = store.put(b"traits").unwrap();
+        assert_eq!(store.get(&uri).unwrap(), b"traits");
+
+        for uri in [
+            "https://elsewhere.xyz/traits".to_string(),
+            "https://ciphertexts.solfhe.xyz/../secret".to_string(),
+            format!("https://ciphertexts.solfhe.xyz/{}", "g".repeat(64)),
+        ] {
+            assert!(matches!(
+                store.get(&uri),
+                Err(CoprocessorError::UnknownCiphertextUri(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_new_rejects_overlong_base_uri() {
+        let dir = env::temp_dir().join("solfhe-store-overlong");
+        let base_uri = "u".repeat(MAX_CIPHERTEXT_URI_LENGTH);
+        assert!(matches!(
+            CiphertextStore::new(dir, base_uri),
+            Err(CoprocessorError::Config(_))
+        ));
+    }
+}
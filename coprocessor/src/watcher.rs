@@ -0,0 +1,386 @@
+use crate::config::Config;
+use crate::error::{CoprocessorError, Result};
+use crate::matcher::{EncryptedTraits, Matcher};
+use crate::store::CiphertextStore;
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::hash::hash;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{read_keypair_file, Keypair, Signature};
+use anchor_client::solana_sdk::signer::Signer;
+use anchor_client::solana_sdk::system_program;
+use anchor_client::{Client, Cluster, Program};
+use solfhe::{AdAccount, FheKeyRegistry, MatchRequest, UserProfile};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The program accounts the watcher reads and the `fulfil_match`
+/// transactions it sends.
+pub trait ProgramClient {
+    fn fhe_key_registry(&self) -> Result<FheKeyRegistry>;
+    fn match_requests(&self) -> Result<Vec<(Pubkey, MatchRequest)>>;
+    fn user_profile(&self, key: Pubkey) -> Result<UserProfile>;
+    fn ad(&self, key: Pubkey) -> Result<AdAccount>;
+    fn fulfil_match(
+        &self,
+        request_key: &Pubkey,
+        request: &MatchRequest,
+        scores_uri: String,
+        score_hashes: Vec<[u8; 32]>,
+    ) -> Result<Signature>;
+}
+
+/// `ProgramClient` talking to a cluster over RPC, signing as the coprocessor.
+pub struct RpcClient {
+    program: Program<Rc<Keypair>>,
+    coprocessor: Pubkey,
+}
+
+impl RpcClient {
+    pub fn new(config: &Config) -> Result<Self> {
+        let keypair = read_keypair_file(&config.keypair_path).map_err(|err| {
+            CoprocessorError::Config(format!(
+                "cannot read keypair {}: {err}",
+                config.keypair_path.display()
+            ))
+        })?;
+        let coprocessor = keypair.pubkey();
+
+        let client = Client::new_with_options(
+            Cluster::Custom(config.rpc_url.clone(), config.ws_url.clone()),
+            Rc::new(keypair),
+            CommitmentConfig::confirmed(),
+        );
+
+        Ok(Self {
+            program: client.program(solfhe::ID)?,
+            coprocessor,
+        })
+    }
+}
+
+impl ProgramClient for RpcClient {
+    fn fhe_key_registry(&self) -> Result<FheKeyRegistry> {
+        Ok(self.program.account(fhe_key_registry_address())?)
+    }
+
+    fn match_requests(&self) -> Result<Vec<(Pubkey, MatchRequest)>> {
+        Ok(self.program.accounts(vec![])?)
+    }
+
+    fn user_profile(&self, key: Pubkey) -> Result<UserProfile> {
+        Ok(self.program.account(key)?)
+    }
+
+    fn ad(&self, key: Pubkey) -> Result<AdAccount> {
+        Ok(self.program.account(key)?)
+    }
+
+    fn fulfil_match(
+        &self,
+        request_key: &Pubkey,
+        request: &MatchRequest,
+        scores_uri: String,
+        score_hashes: Vec<[u8; 32]>,
+    ) -> Result<Signature> {
+        let (state, _) = Pubkey::find_program_address(&[b"state"], &solfhe::ID);
+        let (matched_ads, _) =
+            Pubkey::find_program_address(&[b"matched_ads", request.user.as_ref()], &solfhe::ID);
+
+        let signature = self
+            .program
+            .request()
+            .accounts(solfhe::accounts::FulfilMatch {
+                state,
+                fhe_key_registry: fhe_key_registry_address(),
+                match_request: *request_key,
+                matched_ads,
+                user: request.user,
+                coprocessor: self.coprocessor,
+                system_program: system_program::ID,
+            })
+            .args(solfhe::instruction::FulfilMatch {
+                scores_uri,
+                score_hashes,
+            })
+            .send()?;
+        Ok(signature)
+    }
+}
+
+/// Polls pending match requests and fulfils them with encrypted scores,
+/// published to `store`.
+pub struct Watcher<C = RpcClient> {
+    client: C,
+    matcher: Matcher,
+    store: CiphertextStore,
+    server_key_hash: [u8; 32],
+    poll_interval: Duration,
+    /// `created_at` of requests that could not be scored, so they are not
+    /// retried every round; a replacement request has a new `created_at`.
+    failed: HashMap<Pubkey, i64>,
+}
+
+impl Watcher {
+    pub fn new(config: &Config, matcher: Matcher, server_key_hash: [u8; 32]) -> Result<Self> {
+        let store = CiphertextStore::new(
+            config.ciphertext_dir.clone(),
+            config.ciphertext_base_uri.clone(),
+        )?;
+        Ok(Watcher::with_client(
+            RpcClient::new(config)?,
+            matcher,
+            store,
+            server_key_hash,
+            config.poll_interval,
+        ))
+    }
+}
+
+impl<C: ProgramClient> Watcher<C> {
+    pub fn with_client(
+        client: C,
+        matcher: Matcher,
+        store: CiphertextStore,
+        server_key_hash: [u8; 32],
+        poll_interval: Duration,
+    ) -> Self {
+        Self {
+            client,
+            matcher,
+            store,
+            server_key_hash,
+            poll_interval,
+            failed: HashMap::new(),
+        }
+    }
+
+    /// Polls forever, sleeping `poll_interval` between rounds.
+    pub fn run(&mut self) -> Result<()> {
+        loop {
+            match self.poll_once() {
+                Ok(fulfilled) if fulfilled > 0 => {
+                    log::info!("fulfilled {fulfilled} match requests")
+                }
+                Ok(_) => {}
+                Err(err) => log::error!("poll failed: {err}"),
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+
+    /// Fulfils every pending, unexpired match request and returns the number
+    /// of requests fulfilled.
+    ///
+    /// Requests are only scored under the registry's current epoch, and only
+    /// if the loaded server key is the registered one. A request that cannot
+    /// be fulfilled is logged and skipped without holding up the others.
+    pub fn poll_once(&mut self) -> Result<usize> {
+        let registry = self.client.fhe_key_registry()?;
+        if registry.server_key_hash != self.server_key_hash {
+            return Err(CoprocessorError::ServerKeyMismatch {
+                epoch: registry.epoch,
+            });
+        }
+
+        let now = unix_timestamp();
+        let requests = self.client.match_requests()?;
+
+        let mut fulfilled = 0;
+        for (request_key, request) in &requests {
+            if request.is_expired(now)
+                || request.key_epoch != registry.epoch
+                || self.failed.get(request_key) == Some(&request.created_at)
+            {
+                continue;
+            }
+
+            let encrypted_scores = match self.score_request(request) {
+                Ok(scores) => scores,
+                Err(err) => {
+                    log::warn!("cannot score match request {request_key}: {err}");
+                    self.failed.insert(*request_key, request.created_at);
+                    continue;
+                }
+            };
+
+            // Publishing and sending failures are usually transient, so the
+            // request is retried next round
+            match self.fulfil(request_key, request, encrypted_scores) {
+                Ok(signature) => {
+                    log::debug!("fulfilled match request {request_key}: {signature}");
+                    fulfilled += 1;
+                }
+                Err(err) => log::warn!("cannot fulfil match request {request_key}: {err}"),
+            }
+        }
+
+        // Forget failures for requests that have since been closed
+        self.failed
+            .retain(|key, _| requests.iter().any(|(request_key, _)| request_key == key));
+
+        Ok(fulfilled)
+    }
+
+    /// Scores the request's profile against each candidate ad, in order.
+    fn score_request(&self, request: &MatchRequest) -> Result<Vec<Vec<u8>>> {
+        let profile = self.client.user_profile(request.user_profile)?;
+        let profile = EncryptedTraits::load(&profile.encrypted_data, &self.store)?;
+
+        request
+            .candidate_ads
+            .iter()
+            .map(|ad_key| {
+                let ad = self.client.ad(*ad_key)?;
+                let targets = EncryptedTraits::load(&ad.encrypted_target_traits, &self.store)?;
+                self.matcher.score(&profile, &targets)
+            })
+            .collect()
+    }
+
+    /// Publishes the scores and records their hashes with `fulfil_match`.
+    fn fulfil(
+        &self,
+        request_key: &Pubkey,
+        request: &MatchRequest,
+        encrypted_scores: Vec<Vec<u8>>,
+    ) -> Result<Signature> {
+        let scores_uri = self.store.put(&bincode::serialize(&encrypted_scores)?)?;
+        let score_hashes = encrypted_scores
+            .iter()
+            .map(|score| hash(score).to_bytes())
+            .collect();
+        self.client
+            .fulfil_match(request_key, request, scores_uri, score_hashes)
+    }
+}
+
+fn fhe_key_registry_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"fhe_key_registry"], &solfhe::ID).0
+}
+
+fn unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_client::anchor_lang::AnchorSerialize;
+    use solfhe::{CiphertextEnvelope, FheScheme, TRAIT_SCHEMA_V1};
+    use std::cell::RefCell;
+    use std::io;
+    use tfhe::shortint::parameters::PARAM_MESSAGE_2_CARRY_2;
+    use tfhe::shortint::prelude::*;
+
+    /// Serves fixed accounts and fails to send for `failing_request`.
+    struct MockClient {
+        registry: FheKeyRegistry,
+        requests: Vec<(Pubkey, MatchRequest)>,
+        profile: UserProfile,
+        ad: AdAccount,
+        failing_request: Pubkey,
+        sent: RefCell<Vec<Pubkey>>,
+    }
+
+    impl ProgramClient for MockClient {
+        fn fhe_key_registry(&self) -> Result<FheKeyRegistry> {
+            Ok(self.registry.clone())
+        }
+
+        fn match_requests(&self) -> Result<Vec<(Pubkey, MatchRequest)>> {
+            Ok(self.requests.clone())
+        }
+
+        fn user_profile(&self, _key: Pubkey) -> Result<UserProfile> {
+            Ok(self.profile.clone())
+        }
+
+        fn ad(&self, _key: Pubkey) -> Result<AdAccount> {
+            Ok(self.ad.clone())
+        }
+
+        fn fulfil_match(
+            &self,
+            request_key: &Pubkey,
+            _request: &MatchRequest,
+            _scores_uri: String,
+            score_hashes: Vec<[u8; 32]>,
+        ) -> Result<Signature> {
+            assert_eq!(score_hashes.len(), 1);
+            if *request_key == self.failing_request {
+                return Err(io::Error::other("send failed").into());
+            }
+            self.sent.borrow_mut().push(*request_key);
+            Ok(Signature::default())
+        }
+    }
+
+    fn envelope(store: &CiphertextStore, client_key: &ClientKey, values: &[u64]) -> Vec<u8> {
+        let ciphertexts: Vec<CiphertextBig> = values.iter().map(|&v| client_key.encrypt(v)).collect();
+        let payload = bincode::serialize(&ciphertexts).unwrap();
+        let payload_uri = store.put(&payload).unwrap();
+        CiphertextEnvelope::new(
+            FheScheme::TfheShortint,
+            1,
+            0,
+            TRAIT_SCHEMA_V1,
+            payload_uri,
+            &payload,
+        )
+        .try_to_vec()
+        .unwrap()
+    }
+
+    fn request() -> (Pubkey, MatchRequest) {
+        let request = MatchRequest {
+            user: Pubkey::new_unique(),
+            user_profile: Pubkey::new_unique(),
+            candidate_ads: vec![Pubkey::new_unique()],
+            created_at: 1,
+            expires_at: i64::MAX,
+            ..MatchRequest::default()
+        };
+        (Pubkey::new_unique(), request)
+    }
+
+    #[test]
+    fn test_poll_once_continues_after_failed_send() {
+        let client_key = ClientKey::new(PARAM_MESSAGE_2_CARRY_2);
+        let dir = std::env::temp_dir().join(format!("solfhe-watcher-{}", std::process::id()));
+        let store = CiphertextStore::new(dir, "https://ciphertexts.solfhe.xyz".into()).unwrap();
+
+        let failing = request();
+        let passing = request();
+        let client = MockClient {
+            registry: FheKeyRegistry {
+                server_key_hash: [9; 32],
+                ..FheKeyRegistry::default()
+            },
+            requests: vec![failing.clone(), passing.clone()],
+            profile: UserProfile {
+                encrypted_data: envelope(&store, &client_key, &[1, 2, 3, 0, 1]),
+                ..UserProfile::default()
+            },
+            ad: AdAccount {
+                encrypted_target_traits: envelope(&store, &client_key, &[1, 0, 3, 0, 2]),
+                ..AdAccount::default()
+            },
+            failing_request: failing.0,
+            sent: RefCell::new(vec![]),
+        };
+
+        let matcher = Matcher::new(ServerKey::new(&client_key));
+        let mut watcher =
+            Watcher::with_client(client, matcher, store, [9; 32], Duration::from_secs(1));
+
+        assert_eq!(watcher.poll_once().unwrap(), 1);
+        assert_eq!(*watcher.client.sent.borrow(), vec![passing.0]);
+        // A failed send is retried next round, unlike a failed scoring
+        assert!(watcher.failed.is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::error::ErrorCode;
+use crate::state::{FheKeyRegistry, MAX_CIPHERTEXT_URI_LENGTH};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+
+/// Current `CiphertextEnvelope` layout version.
+pub const CIPHERTEXT_ENVELOPE_VERSION: u8 = 1;
+
+/// Trait schema v1: age, location, interests, device and language buckets.
+pub const TRAIT_SCHEMA_V1: u16 = 1;
+pub const FHE_TRAITS_COUNT: usize = 5; // Number of encrypted traits in schema v1
+
+/// TFHE flavour the payload was produced with.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FheScheme {
+    /// Bincode `Vec<tfhe::shortint::CiphertextBig>`.
+    TfheShortint,
+    /// Bincode `Vec<tfhe::integer::RadixCiphertext>`.
+    TfheInteger,
+}
+
+/// Self-describing reference to FHE ciphertexts submitted to the program.
+///
+/// Every ciphertext records the scheme, parameter set and key epoch it was
+/// produced under, plus the trait schema it encodes, so that a parameter or
+/// schema migration is rejected instead of silently corrupting matching.
+///
+/// A ciphertext is several kilobytes, so the payload itself cannot fit in a
+/// transaction. It is uploaded to `payload_uri` and pinned by its length and
+/// checksum; the coprocessor fetches it and checks it with `verify_payload`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CiphertextEnvelope {
+    pub version: u8,
+    pub scheme: FheScheme,
+    /// `FheKeyRegistry::parameter_set` the ciphertexts were encrypted with.
+    pub parameter_set: u16,
+    /// `FheKeyRegistry::epoch` the ciphertexts were encrypted under.
+    pub key_epoch: u64,
+    pub trait_schema: u16,
+    /// Where the payload, the bincode-serialized ciphertexts, is stored.
+    pub payload_uri: String,
+    pub payload_len: u32,
+    /// SHA-256 of the payload.
+    pub checksum: [u8; 32],
+}
+
+impl CiphertextEnvelope {
+    /// Serialized size of everything but the URI bytes.
+    pub const HEADER_SIZE: usize = 1 // version
+        + 1 // scheme
+        + 2 // parameter_set
+        + 8 // key_epoch
+        + 2 // trait_schema
+        + 4 // payload_uri length
+        + 4 // payload_len
+        + 32; // checksum
+
+    /// Describes `payload`, uploaded to `payload_uri`, in a current-version
+    /// envelope.
+    pub fn new(
+        scheme: FheScheme,
+        parameter_set: u16,
+        key_epoch: u64,
+        trait_schema: u16,
+        payload_uri: String,
+        payload: &[u8],
+    ) -> Self {
+        Self {
+            version: CIPHERTEXT_ENVELOPE_VERSION,
+            scheme,
+            parameter_set,
+            key_epoch,
+            trait_schema,
+            payload_uri,
+            payload_len: payload.len() as u32,
+            checksum: hash(payload).to_bytes(),
+        }
+    }
+
+    /// Decodes an envelope previously stored in an account.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        Self::try_from_slice(data).map_err(|_| error!(ErrorCode::InvalidCiphertextEnvelope))
+    }
+
+    /// Checks the envelope against the registered network keys and returns
+    /// its serialized form, bounded by `max_size`. The payload is off-chain,
+    /// so its contents are only checked by `verify_payload`.
+    pub fn validate(&self, registry: &FheKeyRegistry, max_size: usize) -> Result<Vec<u8>> {
+        require!(
+            self.version == CIPHERTEXT_ENVELOPE_VERSION,
+            ErrorCode::UnsupportedEnvelopeVersion
+        );
+        require!(
+            self.parameter_set == registry.parameter_set,
+            ErrorCode::FheParameterSetMismatch
+        );
+        require!(self.key_epoch == registry.epoch, ErrorCode::StaleKeyEpoch);
+        require!(
+            !self.payload_uri.is_empty() && self.payload_uri.len() <= MAX_CIPHERTEXT_URI_LENGTH,
+            ErrorCode::InvalidCiphertextUri
+        );
+        require!(
+            Self::HEADER_SIZE + self.payload_uri.len() <= max_size,
+            ErrorCode::InvalidFheEncryption
+        );
+        trait_count(self.trait_schema).ok_or(ErrorCode::UnknownTraitSchema)?;
+        require!(self.payload_len > 8, ErrorCode::InvalidFheEncryption);
+
+        self.try_to_vec()
+            .map_err(|_| error!(ErrorCode::InvalidCiphertextEnvelope))
+    }
+
+    /// Checks a payload fetched from `payload_uri` against the envelope.
+    pub fn verify_payload(&self, payload: &[u8]) -> Result<()> {
+        require!(
+            payload.len() == self.payload_len as usize && hash(payload).to_bytes() == self.checksum,
+            ErrorCode::EnvelopeChecksumMismatch
+        );
+
+        // Both schemes serialize a bincode Vec, whose u64 length prefix must
+        // match the trait count of the schema
+        let expected = trait_count(self.trait_schema).ok_or(ErrorCode::UnknownTraitSchema)?;
+        require!(payload.len() > 8, ErrorCode::InvalidFheEncryption);
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&payload[..8]);
+        require!(
+            u64::from_le_bytes(count) == expected as u64,
+            ErrorCode::InvalidTargetTraits
+        );
+        Ok(())
+    }
+}
+
+/// Number of encrypted traits a trait schema carries.
+pub fn trait_count(trait_schema: u16) -> Option<usize> {
+    match trait_schema {
+        TRAIT_SCHEMA_V1 => Some(FHE_TRAITS_COUNT),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD_URI: &str = "https://ciphertexts.solfhe.xyz/traits";
+
+    fn registry() -> FheKeyRegistry {
+        FheKeyRegistry {
+            parameter_set: 1,
+            epoch: 3,
+            ..FheKeyRegistry::default()
+        }
+    }
+
+    fn payload(count: u64) -> Vec<u8> {
+        let mut payload = count.to_le_bytes().to_vec();
+        payload.extend_from_slice(&[7; 64]);
+        payload
+    }
+
+    fn envelope() -> CiphertextEnvelope {
+        CiphertextEnvelope::new(
+            FheScheme::TfheShortint,
+            1,
+            3,
+            TRAIT_SCHEMA_V1,
+            PAYLOAD_URI.to_string(),
+            &payload(FHE_TRAITS_COUNT as u64),
+        )
+    }
+
+    #[test]
+    fn test_validate_round_trips() {
+        let envelope = envelope();
+        let bytes = envelope.validate(&registry(), 1000).unwrap();
+        assert_eq!(CiphertextEnvelope::from_bytes(&bytes).unwrap(), envelope);
+        assert_eq!(
+            bytes.len(),
+            CiphertextEnvelope::HEADER_SIZE + PAYLOAD_URI.len()
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_stale_epoch() {
+        let mut envelope = envelope();
+        envelope.key_epoch = 2;
+        assert_eq!(
+            envelope.validate(&registry(), 1000).unwrap_err(),
+            ErrorCode::StaleKeyEpoch.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_parameter_set_mismatch() {
+        let mut envelope = envelope();
+        envelope.parameter_set = 2;
+        assert_eq!(
+            envelope.validate(&registry(), 1000).unwrap_err(),
+            ErrorCode::FheParameterSetMismatch.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_uri() {
+        let mut envelope = envelope();
+        envelope.payload_uri = String::new();
+        assert_eq!(
+            envelope.validate(&registry(), 1000).unwrap_err(),
+            ErrorCode::InvalidCiphertextUri.into()
+        );
+
+        envelope.payload_uri = "u".repeat(MAX_CIPHERTEXT_URI_LENGTH + 1);
+        assert_eq!(
+            envelope.validate(&registry(), 1000).unwrap_err(),
+            ErrorCode::InvalidCiphertextUri.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_oversized_envelope() {
+        let envelope = envelope();
+        let size = CiphertextEnvelope::HEADER_SIZE + envelope.payload_uri.len();
+        assert_eq!(
+            envelope.validate(&registry(), size - 1).unwrap_err(),
+            ErrorCode::InvalidFheEncryption.into()
+        );
+    }
+
+    #[test]
+    fn test_verify_payload_accepts_original() {
+        envelope()
+            .verify_payload(&payload(FHE_TRAITS_COUNT as u64))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_verify_payload_rejects_tampered_payload() {
+        let mut tampered = payload(FHE_TRAITS_COUNT as u64);
+        tampered[10] ^= 1;
+        assert_eq!(
+            envelope().verify_payload(&tampered).unwrap_err(),
+            ErrorCode::EnvelopeChecksumMismatch.into()
+        );
+
+        tampered = payload(FHE_TRAITS_COUNT as u64);
+        tampered.push(0);
+        assert_eq!(
+            envelope().verify_payload(&tampered).unwrap_err(),
+            ErrorCode::EnvelopeChecksumMismatch.into()
+        );
+    }
+
+    #[test]
+    fn test_verify_payload_rejects_wrong_trait_count() {
+        let payload = payload(FHE_TRAITS_COUNT as u64 + 1);
+        let envelope = CiphertextEnvelope::new(
+            FheScheme::TfheShortint,
+            1,
+            3,
+            TRAIT_SCHEMA_V1,
+            PAYLOAD_URI.to_string(),
+            &payload,
+        );
+        assert_eq!(
+            envelope.verify_payload(&payload).unwrap_err(),
+            ErrorCode::InvalidTargetTraits.into()
+        );
+    }
+}
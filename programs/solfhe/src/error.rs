@@ -0,0 +1,422 @@
+use anchor_lang::prelude::*;
+
+/// Program errors. Discriminants are fixed and grouped by `ErrorCategory`, one
+/// block of 100 per category, so codes stay stable as variants are added;
+/// never reuse or renumber a released code. The on-chain code is the
+/// discriminant plus Anchor's `ERROR_CODE_OFFSET` (6000).
+#[error_code]
+pub enum ErrorCode {
+    // General (0..100). 0 was `BumpNotFound`, unused since bumps became
+    // typed fields of `Context::bumps`
+    #[msg("Arithmetic overflow")]
+    Overflow = 1,
+    #[msg("Protocol is paused")]
+    ProtocolPaused = 2,
+    #[msg("Protocol is not paused")]
+    ProtocolNotPaused = 3,
+
+    // Validation (100..200)
+    #[msg("Invalid advertiser name")]
+    InvalidAdvertiserName = 100,
+    #[msg("Invalid advertiser email")]
+    InvalidAdvertiserEmail = 101,
+    #[msg("Invalid ad content")]
+    InvalidAdContent = 102,
+    #[msg("Invalid ad duration")]
+    InvalidAdDuration = 103,
+    #[msg("Ad is not active")]
+    AdNotActive = 104,
+    #[msg("Match timeout out of bounds")]
+    InvalidMatchTimeout = 105,
+    #[msg("Invalid match candidates")]
+    InvalidMatchCandidates = 106,
+    #[msg("A match request is already pending for this user")]
+    MatchRequestPending = 107,
+    #[msg("Match request has expired")]
+    MatchRequestExpired = 108,
+    #[msg("Number of scores does not match the candidate ads")]
+    ScoreCountMismatch = 109,
+    #[msg("Ad is already active")]
+    AdAlreadyActive = 110,
+    #[msg("Ad has expired")]
+    AdExpired = 111,
+    #[msg("Settlement count must be positive")]
+    InvalidSettlementCount = 112,
+    #[msg("Basis points exceed 10000 in total")]
+    InvalidBasisPoints = 113,
+    #[msg("Ad is not among the user's matched ads")]
+    AdNotMatched = 114,
+    #[msg("Amount must be positive")]
+    InvalidAmount = 115,
+    #[msg("Invalid protocol parameters")]
+    InvalidProtocolParams = 116,
+    #[msg("Advertiser is not active")]
+    AdvertiserNotActive = 117,
+    // 118 was `IncompleteAdList`, unused since deactivation stopped pausing
+    // each ad
+    #[msg("Advertiser still has open ads or campaigns")]
+    AdvertiserHasOpenAds = 119,
+    #[msg("Email commitment does not match the advertiser's")]
+    EmailCommitmentMismatch = 120,
+    #[msg("Advertiser name is not in Unicode NFC form")]
+    NameNotNormalized = 121,
+    #[msg("Advertiser name contains a disallowed character")]
+    DisallowedNameCharacter = 122,
+    #[msg("Advertiser name is too long")]
+    NameTooLong = 123,
+    #[msg("Invalid email address syntax")]
+    InvalidEmailSyntax = 124,
+    #[msg("Email address is too long")]
+    EmailTooLong = 125,
+    #[msg("Ad is waiting for review")]
+    AdPendingReview = 126,
+    #[msg("Ad is not waiting for review")]
+    AdNotPendingReview = 127,
+    #[msg("Ad does not belong to this campaign")]
+    CampaignMismatch = 128,
+    #[msg("Campaign is not active")]
+    CampaignNotActive = 129,
+    #[msg("Campaign has ended")]
+    CampaignExpired = 130,
+    #[msg("Campaign still has open ads")]
+    CampaignHasOpenAds = 131,
+    #[msg("Invalid ad start time")]
+    InvalidStartTime = 132,
+    #[msg("Ad has not started yet")]
+    AdNotStarted = 133,
+    #[msg("Ad has not ended yet")]
+    AdNotExpired = 134,
+    #[msg("Ad has already expired")]
+    AdAlreadyExpired = 135,
+    #[msg("Campaign has not started yet")]
+    CampaignNotStarted = 136,
+
+    // FHE (200..300)
+    #[msg("Invalid FHE encryption")]
+    InvalidFheEncryption = 200,
+    #[msg("Invalid FHE key parameters")]
+    InvalidFheKeys = 201,
+    #[msg("Ciphertext was encrypted under a stale FHE key epoch")]
+    StaleKeyEpoch = 202,
+    #[msg("Invalid target traits")]
+    InvalidTargetTraits = 203,
+    #[msg("Malformed ciphertext envelope")]
+    InvalidCiphertextEnvelope = 204,
+    #[msg("Unsupported ciphertext envelope version")]
+    UnsupportedEnvelopeVersion = 205,
+    #[msg("Ciphertext parameter set does not match the registered FHE keys")]
+    FheParameterSetMismatch = 206,
+    #[msg("Unknown trait schema")]
+    UnknownTraitSchema = 207,
+    #[msg("Ciphertext envelope checksum mismatch")]
+    EnvelopeChecksumMismatch = 208,
+    #[msg("Ad and profile use different trait schemas")]
+    TraitSchemaMismatch = 209,
+    #[msg("Invalid off-chain ciphertext URI")]
+    InvalidCiphertextUri = 210,
+
+    // Payment (300..400)
+    #[msg("Insufficient funds")]
+    InsufficientFunds = 300,
+    #[msg("Ad budget below the minimum")]
+    InsufficientAdBudget = 301,
+    #[msg("Ad budget is still held in the shared treasury")]
+    AdVaultNotMigrated = 302,
+    #[msg("Ad budget has already been moved to its vault")]
+    AdVaultAlreadyMigrated = 303,
+    #[msg("No rewards to claim")]
+    NoRewardsToClaim = 304,
+    #[msg("Payment mint is already set")]
+    PaymentMintAlreadySet = 305,
+    #[msg("Stake is still locked")]
+    StakeLocked = 306,
+    #[msg("Ad budget exceeds the advertiser's reputation cap")]
+    BudgetExceedsReputationCap = 307,
+    #[msg("Ad has reached its spend limit for the day")]
+    DailyCapReached = 308,
+    #[msg("Payment mint is not accepted")]
+    PaymentMintNotAccepted = 309,
+    #[msg("Payment mint is already accepted")]
+    PaymentMintAlreadyAccepted = 310,
+    #[msg("Payment mint registry is full")]
+    PaymentMintRegistryFull = 311,
+    #[msg("Daily cap is below the cost of one settlement or above the budget")]
+    InvalidDailyCap = 312,
+    #[msg("Ad's vault bump does not derive a vault address")]
+    InvalidAdVault = 313,
+
+    // Auth (400..500)
+    #[msg("Unauthorized action")]
+    Unauthorized = 400,
+    #[msg("Missing Ed25519 signature instruction")]
+    MissingEd25519Instruction = 401,
+    #[msg("Profile signature does not match the user or profile data")]
+    InvalidProfileSignature = 402,
+
+    // Cross-chain (500..600)
+    #[msg("Invalid cross-chain message")]
+    InvalidCrossChainMessage = 500,
+    #[msg("Unknown cross-chain message type")]
+    UnknownMessageType = 501,
+}
+
+/// Broad class of an `ErrorCode`, derived from its discriminant block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCategory {
+    General,
+    Validation,
+    Fhe,
+    Payment,
+    Auth,
+    CrossChain,
+}
+
+impl ErrorCode {
+    /// Every variant, in discriminant order.
+    pub const ALL: [ErrorCode; 69] = [
+        ErrorCode::Overflow,
+        ErrorCode::ProtocolPaused,
+        ErrorCode::ProtocolNotPaused,
+        ErrorCode::InvalidAdvertiserName,
+        ErrorCode::InvalidAdvertiserEmail,
+        ErrorCode::InvalidAdContent,
+        ErrorCode::InvalidAdDuration,
+        ErrorCode::AdNotActive,
+        ErrorCode::InvalidMatchTimeout,
+        ErrorCode::InvalidMatchCandidates,
+        ErrorCode::MatchRequestPending,
+        ErrorCode::MatchRequestExpired,
+        ErrorCode::ScoreCountMismatch,
+        ErrorCode::AdAlreadyActive,
+        ErrorCode::AdExpired,
+        ErrorCode::InvalidSettlementCount,
+        ErrorCode::InvalidBasisPoints,
+        ErrorCode::AdNotMatched,
+        ErrorCode::InvalidAmount,
+        ErrorCode::InvalidProtocolParams,
+        ErrorCode::AdvertiserNotActive,
+        ErrorCode::AdvertiserHasOpenAds,
+        ErrorCode::EmailCommitmentMismatch,
+        ErrorCode::NameNotNormalized,
+        ErrorCode::DisallowedNameCharacter,
+        ErrorCode::NameTooLong,
+        ErrorCode::InvalidEmailSyntax,
+        ErrorCode::EmailTooLong,
+        ErrorCode::AdPendingReview,
+        ErrorCode::AdNotPendingReview,
+        ErrorCode::CampaignMismatch,
+        ErrorCode::CampaignNotActive,
+        ErrorCode::CampaignExpired,
+        ErrorCode::CampaignHasOpenAds,
+        ErrorCode::InvalidStartTime,
+        ErrorCode::AdNotStarted,
+        ErrorCode::AdNotExpired,
+        ErrorCode::AdAlreadyExpired,
+        ErrorCode::CampaignNotStarted,
+        ErrorCode::InvalidFheEncryption,
+        ErrorCode::InvalidFheKeys,
+        ErrorCode::StaleKeyEpoch,
+        ErrorCode::InvalidTargetTraits,
+        ErrorCode::InvalidCiphertextEnvelope,
+        ErrorCode::UnsupportedEnvelopeVersion,
+        ErrorCode::FheParameterSetMismatch,
+        ErrorCode::UnknownTraitSchema,
+        ErrorCode::EnvelopeChecksumMismatch,
+        ErrorCode::TraitSchemaMismatch,
+        ErrorCode::InvalidCiphertextUri,
+        ErrorCode::InsufficientFunds,
+        ErrorCode::InsufficientAdBudget,
+        ErrorCode::AdVaultNotMigrated,
+        ErrorCode::AdVaultAlreadyMigrated,
+        ErrorCode::NoRewardsToClaim,
+        ErrorCode::PaymentMintAlreadySet,
+        ErrorCode::StakeLocked,
+        ErrorCode::BudgetExceedsReputationCap,
+        ErrorCode::DailyCapReached,
+        ErrorCode::PaymentMintNotAccepted,
+        ErrorCode::PaymentMintAlreadyAccepted,
+        ErrorCode::PaymentMintRegistryFull,
+        ErrorCode::InvalidDailyCap,
+        ErrorCode::InvalidAdVault,
+        ErrorCode::Unauthorized,
+        ErrorCode::MissingEd25519Instruction,
+        ErrorCode::InvalidProfileSignature,
+        ErrorCode::InvalidCrossChainMessage,
+        ErrorCode::UnknownMessageType,
+    ];
+
+    /// Decodes the custom error code of a failed transaction, including
+    /// Anchor's offset.
+    pub fn from_u32(code: u32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|error| u32::from(*error) == code)
+    }
+
+    pub fn category(self) -> ErrorCategory {
+        match (self as u32) / 100 {
+            0 => ErrorCategory::General,
+            1 => ErrorCategory::Validation,
+            2 => ErrorCategory::Fhe,
+            3 => ErrorCategory::Payment,
+            4 => ErrorCategory::Auth,
+            _ => ErrorCategory::CrossChain,
+        }
+    }
+
+    /// Message suitable for showing to end users; `#[msg]` is aimed at
+    /// developers reading program logs.
+    pub fn user_message(self) -> &'static str {
+        match self {
+            ErrorCode::Overflow => "Something went wrong on our side. Please try again later.",
+            ErrorCode::ProtocolPaused => {
+                "solFHE is paused for maintenance. Please try again later."
+            }
+            ErrorCode::ProtocolNotPaused => "solFHE is already running.",
+            ErrorCode::InvalidAdvertiserName => "Please enter an advertiser name.",
+            ErrorCode::InvalidAdvertiserEmail => "Please enter a valid email address.",
+            ErrorCode::InvalidAdContent => "Please enter shorter ad content.",
+            ErrorCode::InvalidAdDuration => "Please choose a different ad duration.",
+            ErrorCode::AdNotActive => "This ad is no longer running.",
+            ErrorCode::InvalidMatchTimeout => "Please choose a different match timeout.",
+            ErrorCode::InvalidMatchCandidates => "Please select fewer ads, each only once.",
+            ErrorCode::MatchRequestPending => {
+                "You already have a match in progress. Please wait for it or cancel it."
+            }
+            ErrorCode::MatchRequestExpired => "Your match request expired. Please try again.",
+            ErrorCode::ScoreCountMismatch => "Matching failed. Please try again.",
+            ErrorCode::AdAlreadyActive => "This ad is already running.",
+            ErrorCode::AdExpired => "This ad has ended. Extend its duration to run it again.",
+            ErrorCode::InvalidSettlementCount => "Nothing to settle.",
+            ErrorCode::InvalidBasisPoints => "Percentages cannot exceed 100%.",
+            ErrorCode::AdNotMatched => "This ad was not matched with the user.",
+            ErrorCode::InvalidAmount => "Please enter an amount greater than zero.",
+            ErrorCode::InvalidProtocolParams => "Please choose consistent protocol parameters.",
+            ErrorCode::AdvertiserNotActive => "This advertiser account is deactivated.",
+            ErrorCode::AdvertiserHasOpenAds => "Please close all of your ads and campaigns first.",
+            ErrorCode::EmailCommitmentMismatch => {
+                "The contact email changed; please verify it again."
+            }
+            ErrorCode::NameNotNormalized => "Please re-enter the advertiser name.",
+            ErrorCode::DisallowedNameCharacter => {
+                "Please use only printable characters in the advertiser name."
+            }
+            ErrorCode::NameTooLong => "Please enter a shorter advertiser name.",
+            ErrorCode::InvalidEmailSyntax | ErrorCode::EmailTooLong => {
+                "Please enter a valid email address."
+            }
+            ErrorCode::AdPendingReview => "This ad will start running once it has been reviewed.",
+            ErrorCode::AdNotPendingReview => "This ad has already been reviewed.",
+            ErrorCode::CampaignMismatch => "Please select the ad's own campaign.",
+            ErrorCode::CampaignNotActive => "This campaign has used up its budget.",
+            ErrorCode::CampaignExpired => "This campaign has ended.",
+            ErrorCode::CampaignHasOpenAds => "Please close all ads of this campaign first.",
+            ErrorCode::InvalidStartTime => {
+                "Please choose a start time that is not in the past and not too far ahead."
+            }
+            ErrorCode::AdNotStarted => "This ad has not started yet.",
+            ErrorCode::AdNotExpired => "This ad is still running.",
+            ErrorCode::AdAlreadyExpired => "This ad has already expired.",
+            ErrorCode::CampaignNotStarted => "This campaign has not started yet.",
+            ErrorCode::InvalidFheEncryption
+            | ErrorCode::InvalidTargetTraits
+            | ErrorCode::InvalidCiphertextEnvelope
+            | ErrorCode::UnsupportedEnvelopeVersion
+            | ErrorCode::FheParameterSetMismatch
+            | ErrorCode::UnknownTraitSchema
+            | ErrorCode::EnvelopeChecksumMismatch
+            | ErrorCode::InvalidCiphertextUri => {
+                "Your encrypted data could not be read. Please update your app and try again."
+            }
+            ErrorCode::InvalidFheKeys => "The encryption keys provided are invalid.",
+            ErrorCode::StaleKeyEpoch => {
+                "The network encryption keys have changed. Please re-encrypt your data."
+            }
+            ErrorCode::TraitSchemaMismatch => {
+                "This ad cannot be matched against your profile. Please update your profile."
+            }
+            ErrorCode::InsufficientFunds => "Your wallet balance is too low.",
+            ErrorCode::InsufficientAdBudget => "Your ad budget is below the minimum.",
+            ErrorCode::AdVaultNotMigrated => "Please migrate this ad's budget to its vault first.",
+            ErrorCode::AdVaultAlreadyMigrated => "This ad's budget is already in its vault.",
+            ErrorCode::NoRewardsToClaim => "You have no rewards to claim yet.",
+            ErrorCode::PaymentMintAlreadySet => "The payment token can no longer be changed.",
+            ErrorCode::StakeLocked => {
+                "Your stake can be withdrawn once your account is deactivated and the cooldown has passed."
+            }
+            ErrorCode::BudgetExceedsReputationCap => {
+                "Please choose a smaller budget; larger budgets unlock as your reputation grows."
+            }
+            ErrorCode::DailyCapReached => "This ad has spent its budget for today.",
+            ErrorCode::PaymentMintNotAccepted => "Please pay with one of the accepted tokens.",
+            ErrorCode::PaymentMintAlreadyAccepted => "This token is already accepted.",
+            ErrorCode::PaymentMintRegistryFull => "The payment token limit has been reached.",
+            ErrorCode::InvalidDailyCap => {
+                "Please choose a daily cap that covers at least one impression or click and does not exceed the budget."
+            }
+            ErrorCode::InvalidAdVault => "This ad's budget could not be found.",
+            ErrorCode::Unauthorized => "You are not allowed to perform this action.",
+            ErrorCode::MissingEd25519Instruction | ErrorCode::InvalidProfileSignature => {
+                "Your profile signature could not be verified. Please sign again."
+            }
+            ErrorCode::InvalidCrossChainMessage | ErrorCode::UnknownMessageType => {
+                "A cross-chain message could not be processed."
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::error::ERROR_CODE_OFFSET;
+
+    #[test]
+    fn test_from_u32_round_trips() {
+        for error in ErrorCode::ALL {
+            let decoded = ErrorCode::from_u32(u32::from(error)).unwrap();
+            assert_eq!(decoded as u32, error as u32);
+        }
+    }
+
+    #[test]
+    fn test_from_u32_rejects_unknown_codes() {
+        assert!(ErrorCode::from_u32(0).is_none());
+        // Retired `BumpNotFound`
+        assert!(ErrorCode::from_u32(ERROR_CODE_OFFSET).is_none());
+        assert!(ErrorCode::from_u32(ERROR_CODE_OFFSET + 99).is_none());
+        assert!(ErrorCode::from_u32(ERROR_CODE_OFFSET + 600).is_none());
+    }
+
+    #[test]
+    fn test_codes_are_stable() {
+        assert_eq!(u32::from(ErrorCode::Overflow), 6001);
+        assert_eq!(u32::from(ErrorCode::InvalidAdvertiserName), 6100);
+        assert_eq!(u32::from(ErrorCode::InvalidFheEncryption), 6200);
+        assert_eq!(u32::from(ErrorCode::InsufficientFunds), 6300);
+        assert_eq!(u32::from(ErrorCode::Unauthorized), 6400);
+        assert_eq!(u32::from(ErrorCode::InvalidCrossChainMessage), 6500);
+    }
+
+    #[test]
+    fn test_all_is_sorted_and_categorised() {
+        assert!(ErrorCode::ALL
+            .windows(2)
+            .all(|pair| (pair[0] as u32) < (pair[1] as u32)));
+        assert_eq!(ErrorCode::Overflow.category(), ErrorCategory::General);
+        assert_eq!(ErrorCode::AdNotActive.category(), ErrorCategory::Validation);
+        assert_eq!(ErrorCode::StaleKeyEpoch.category(), ErrorCategory::Fhe);
+        assert_eq!(
+            ErrorCode::InsufficientAdBudget.category(),
+            ErrorCategory::Payment
+        );
+        assert_eq!(
+            ErrorCode::InvalidProfileSignature.category(),
+            ErrorCategory::Auth
+        );
+        assert_eq!(
+            ErrorCode::UnknownMessageType.category(),
+            ErrorCategory::CrossChain
+        );
+    }
+}
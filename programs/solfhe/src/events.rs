@@ -0,0 +1,507 @@
+use crate::reputation::ReputationReason;
+use crate::state::{AcceptedMint, Pacing, ProtocolParams};
+use anchor_lang::prelude::*;
+
+// Every event carries `sequence`, taken from `StateAccount::next_event_sequence`.
+// Sequences start at 1 and increase by one per emitted event, so indexers can
+// detect missed logs and replay state transitions in order.
+
+#[event]
+pub struct ProgramInitialized {
+    pub sequence: u64,
+    pub authority: Pubkey,
+    pub coprocessor: Pubkey,
+    pub fhe_key_epoch: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityProposed {
+    pub sequence: u64,
+    pub authority: Pubkey,
+    /// `Pubkey::default()` when a pending handover was cancelled.
+    pub pending_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub sequence: u64,
+    pub previous_authority: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentMintSet {
+    pub sequence: u64,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentMintAccepted {
+    pub sequence: u64,
+    pub accepted: AcceptedMint,
+    pub fee_vault: Pubkey,
+    pub rewards_vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentMintUpdated {
+    pub sequence: u64,
+    pub accepted: AcceptedMint,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolConfigUpdated {
+    pub sequence: u64,
+    pub params: ProtocolParams,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolPaused {
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolResumed {
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsVaultInitialized {
+    pub sequence: u64,
+    pub rewards_vault: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FheKeysRotated {
+    pub sequence: u64,
+    pub epoch: u64,
+    pub public_key_hash: [u8; 32],
+    pub server_key_hash: [u8; 32],
+    pub key_uri: String,
+    pub parameter_set: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdvertiserRegistered {
+    pub sequence: u64,
+    pub advertiser: Pubkey,
+    pub authority: Pubkey,
+    pub name: String,
+    pub email_commitment: [u8; 32],
+    /// Lamports locked in the advertiser's stake.
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdvertiserUpdated {
+    pub sequence: u64,
+    pub advertiser: Pubkey,
+    pub authority: Pubkey,
+    pub name: String,
+    pub email_commitment: [u8; 32],
+    /// Whether the update kept a verified email.
+    pub email_verified: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdvertiserEmailVerified {
+    pub sequence: u64,
+    pub advertiser: Pubkey,
+    pub email_commitment: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdvertiserDeactivated {
+    pub sequence: u64,
+    pub advertiser: Pubkey,
+    pub authority: Pubkey,
+    /// When the stake can be withdrawn.
+    pub unlocks_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdvertiserSlashed {
+    pub sequence: u64,
+    pub advertiser: Pubkey,
+    pub amount: u64,
+    pub reputation_penalty: u16,
+    /// Score after the penalty; the advertiser is deactivated at zero.
+    pub reputation_score: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdvertiserClosed {
+    pub sequence: u64,
+    pub advertiser: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReputationUpdated {
+    pub sequence: u64,
+    pub advertiser: Pubkey,
+    pub reason: ReputationReason,
+    /// Score after the change; decay since the last update is applied first.
+    pub reputation_score: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeWithdrawn {
+    pub sequence: u64,
+    pub advertiser: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdCreated {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    pub content: String,
+    pub key_epoch: u64,
+    pub trait_schema: u16,
+    pub budget: u64,
+    pub duration: i64,
+    /// When the ad starts running; it ends `duration` seconds later.
+    pub start_at: i64,
+    /// Mint the budget was paid in.
+    pub mint: Pubkey,
+    /// Set for ads of low-reputation advertisers, which wait for
+    /// `approve_ad` before they run.
+    pub pending_review: bool,
+    /// Most the ad spends per day; 0 for no cap.
+    pub daily_cap: u64,
+    pub pacing: Pacing,
+    /// `Pubkey::default()` for a standalone ad. A campaign ad has no budget
+    /// of its own and spends from the campaign.
+    pub campaign: Pubkey,
+    pub created_at: i64,
+}
+
+#[event]
+pub struct AdApproved {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdPaused {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdResumed {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdContentUpdated {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    pub content: String,
+    /// Set when the new content waits for `approve_ad`.
+    pub pending_review: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdDurationExtended {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    /// Total duration in seconds, measured from `start_at`.
+    pub duration: i64,
+    pub ends_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdClosed {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    pub spent_budget: u64,
+    pub timestamp: i64,
+}
+
+/// An ended ad was marked inactive by `expire_ad` and its unspent budget
+/// refunded.
+#[event]
+pub struct AdExpired {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    pub spent_budget: u64,
+    pub refund: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdBudgetToppedUp {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    /// Amount added to the budget, net of the protocol fee.
+    pub amount: u64,
+    /// Ad budget after the top-up.
+    pub budget: u64,
+    pub timestamp: i64,
+}
+
+/// An ad spent its daily limit; it is not settled again until `resets_at`.
+#[event]
+pub struct AdDailyCapReached {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    pub day_spent: u64,
+    pub resets_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignCreated {
+    pub sequence: u64,
+    pub campaign: Pubkey,
+    pub advertiser: Pubkey,
+    pub mint: Pubkey,
+    pub key_epoch: u64,
+    pub trait_schema: u16,
+    pub budget: u64,
+    pub duration: i64,
+    /// When member ads start running; the campaign ends `duration` seconds
+    /// later.
+    pub start_at: i64,
+    pub created_at: i64,
+}
+
+/// A campaign's shared budget ran out; none of its ads can be settled.
+#[event]
+pub struct CampaignPaused {
+    pub sequence: u64,
+    pub campaign: Pubkey,
+    pub advertiser: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignClosed {
+    pub sequence: u64,
+    pub campaign: Pubkey,
+    pub advertiser: Pubkey,
+    /// Campaign totals over all of its ads.
+    pub spent_budget: u64,
+    pub impressions: u64,
+    pub clicks: u64,
+    /// Token account the unspent budget was paid into.
+    pub recipient: Pubkey,
+    pub refund: u64,
+    pub timestamp: i64,
+}
+
+/// Unspent budget of a legacy ad moved from the shared treasury into its vault.
+#[event]
+pub struct AdVaultMigrated {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    pub ad_vault: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Unspent budget returned to an advertiser, e.g. when an ad is closed.
+#[event]
+pub struct BudgetRefunded {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub advertiser: Pubkey,
+    /// Token account the refund was paid into.
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PublisherRegistered {
+    pub sequence: u64,
+    pub publisher: Pubkey,
+    pub publisher_account: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PublisherRevoked {
+    pub sequence: u64,
+    pub publisher: Pubkey,
+    pub publisher_account: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SettlementRatesUpdated {
+    pub sequence: u64,
+    pub cpm_rate: u64,
+    pub cpc_rate: u64,
+    pub user_reward_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ImpressionSettled {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub publisher: Pubkey,
+    pub user: Pubkey,
+    pub count: u32,
+    /// Amount charged to the ad; less than the rate once the budget or the
+    /// day's allowance runs out.
+    pub cost: u64,
+    /// Part of `cost` paid into the fee vault.
+    pub protocol_fee: u64,
+    /// Part of `cost` accrued to the user; the rest is paid to the publisher.
+    pub user_reward: u64,
+    /// Ad totals after settlement.
+    pub spent_budget: u64,
+    pub impressions: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClickSettled {
+    pub sequence: u64,
+    pub ad: Pubkey,
+    pub publisher: Pubkey,
+    pub user: Pubkey,
+    pub count: u32,
+    /// Amount charged to the ad; less than the rate once the budget or the
+    /// day's allowance runs out.
+    pub cost: u64,
+    /// Part of `cost` paid into the fee vault.
+    pub protocol_fee: u64,
+    /// Part of `cost` accrued to the user; the rest is paid to the publisher.
+    pub user_reward: u64,
+    /// Ad totals after settlement.
+    pub spent_budget: u64,
+    pub clicks: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeVaultInitialized {
+    pub sequence: u64,
+    pub fee_vault: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolFeeUpdated {
+    pub sequence: u64,
+    pub protocol_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+/// Protocol fee taken out of an ad deposit. Fees on settlements are reported
+/// in `ImpressionSettled` and `ClickSettled`.
+#[event]
+pub struct ProtocolFeeCollected {
+    pub sequence: u64,
+    /// Ad the deposit funded; `Pubkey::default()` for a campaign deposit.
+    pub ad: Pubkey,
+    /// Campaign the deposit funded, if any.
+    pub campaign: Option<Pubkey>,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub sequence: u64,
+    pub mint: Pubkey,
+    /// Token account the fees were paid into.
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub user_rewards: Pubkey,
+    pub mint: Pubkey,
+    /// Token account the rewards were paid into.
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserProfileSubmitted {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub user_profile: Pubkey,
+    pub key_epoch: u64,
+    pub is_new: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MatchRequested {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub match_request: Pubkey,
+    pub candidate_ads: Vec<Pubkey>,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdsMatched {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub match_request: Pubkey,
+    pub matched_ads: Pubkey,
+    pub match_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MatchCancelled {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub match_request: Pubkey,
+    pub expired: bool,
+    pub timestamp: i64,
+}
@@ -0,0 +1,36 @@
+use crate::error::ErrorCode;
+use crate::events::AuthorityTransferred;
+use crate::state::StateAccount;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.pending_authority == new_authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    pub new_authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AcceptAuthority>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let previous_authority = state.authority;
+
+    state.authority = ctx.accounts.new_authority.key();
+    state.pending_authority = Pubkey::default();
+    state.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(AuthorityTransferred {
+        sequence: state.next_event_sequence()?,
+        previous_authority,
+        authority: state.authority,
+        timestamp: state.last_updated,
+    });
+
+    msg!("Authority transferred to {}", state.authority);
+    Ok(())
+}
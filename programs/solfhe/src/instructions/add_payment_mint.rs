@@ -0,0 +1,91 @@
+use crate::error::ErrorCode;
+use crate::events::PaymentMintAccepted;
+use crate::state::{AcceptedMint, PaymentMintRegistry, StateAccount};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct AddPaymentMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PaymentMintRegistry::SPACE,
+        seeds = [b"payment_mint_registry"],
+        bump
+    )]
+    pub payment_mint_registry: Account<'info, PaymentMintRegistry>,
+
+    /// The primary payment mint is accepted without an entry
+    #[account(constraint = mint.key() != state.payment_mint @ ErrorCode::PaymentMintAlreadyAccepted)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"fee_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = state,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"rewards_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = state,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accepts `accepted.mint` for ad and campaign budgets, creating its fee
+/// vault and rewards vault.
+pub fn handler(ctx: Context<AddPaymentMint>, accepted: AcceptedMint) -> Result<()> {
+    require_keys_eq!(
+        accepted.mint,
+        ctx.accounts.mint.key(),
+        ErrorCode::InvalidProtocolParams
+    );
+    accepted.validate()?;
+
+    let state = &mut ctx.accounts.state;
+    // Otherwise set_payment_mint could later make this mint the primary one,
+    // moving its vaults to the unkeyed addresses
+    require!(
+        state.payment_mint != Pubkey::default(),
+        ErrorCode::PaymentMintNotAccepted
+    );
+    let registry = &mut ctx.accounts.payment_mint_registry;
+    let now = Clock::get()?.unix_timestamp;
+
+    registry.add(accepted)?;
+    registry.bump = ctx.bumps.payment_mint_registry;
+    registry.last_updated = now;
+
+    emit!(PaymentMintAccepted {
+        sequence: state.next_event_sequence()?,
+        accepted,
+        fee_vault: ctx.accounts.fee_vault.key(),
+        rewards_vault: ctx.accounts.rewards_vault.key(),
+        timestamp: now,
+    });
+
+    msg!("Payment mint accepted: {}", accepted.mint);
+    Ok(())
+}
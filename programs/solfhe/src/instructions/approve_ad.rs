@@ -0,0 +1,50 @@
+use crate::error::ErrorCode;
+use crate::events::AdApproved;
+use crate::state::{AdAccount, AdvertiserAccount, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ApproveAd<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(constraint = advertiser.is_active @ ErrorCode::AdvertiserNotActive)]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        mut,
+        has_one = advertiser @ ErrorCode::Unauthorized,
+        constraint = ad.pending_review @ ErrorCode::AdNotPendingReview,
+    )]
+    pub ad: Account<'info, AdAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Lets an ad of a low-reputation advertiser run after review.
+pub fn handler(ctx: Context<ApproveAd>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let ad = &mut ctx.accounts.ad;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(now < ad.ends_at()?, ErrorCode::AdExpired);
+
+    ad.pending_review = false;
+    ad.is_active = ad.is_campaign_ad() || ad.remaining_budget()? > 0;
+    ad.last_updated = now;
+
+    emit!(AdApproved {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: ad.advertiser,
+        timestamp: now,
+    });
+
+    msg!("Ad approved: {}", ad.key());
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::error::ErrorCode;
+use crate::events::MatchCancelled;
+use crate::state::{MatchRequest, StateAccount};
+use anchor_lang::prelude::*;
+
+/// Not gated by the protocol pause, so users can always reclaim the rent of
+/// a pending request.
+#[derive(Accounts)]
+pub struct CancelMatch<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"match_request", user.key().as_ref()],
+        bump = match_request.bump,
+        has_one = user @ ErrorCode::Unauthorized,
+    )]
+    pub match_request: Account<'info, MatchRequest>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<CancelMatch>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let match_request = &ctx.accounts.match_request;
+    let now = Clock::get()?.unix_timestamp;
+
+    emit!(MatchCancelled {
+        sequence: state.next_event_sequence()?,
+        user: match_request.user,
+        match_request: match_request.key(),
+        expired: match_request.is_expired(now),
+        timestamp: now,
+    });
+
+    msg!("Match request cancelled for user: {}", match_request.user);
+    Ok(())
+}
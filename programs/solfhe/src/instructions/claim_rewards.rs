@@ -0,0 +1,85 @@
+use crate::error::ErrorCode;
+use crate::events::RewardsClaimed;
+use crate::state::{StateAccount, UserRewards};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user_rewards", user.key().as_ref(), state.mint_seed(&user_rewards.mint)],
+        bump = user_rewards.bump,
+        has_one = user @ ErrorCode::Unauthorized,
+    )]
+    pub user_rewards: Account<'info, UserRewards>,
+
+    #[account(
+        mut,
+        constraint = rewards_vault.mint == user_rewards.mint,
+        seeds = [b"rewards_vault", state.mint_seed(&user_rewards.mint)],
+        bump
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key(),
+        constraint = user_token_account.mint == user_rewards.mint,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<ClaimRewards>) -> Result<()> {
+    let amount = ctx.accounts.user_rewards.accrued;
+    require!(amount > 0, ErrorCode::NoRewardsToClaim);
+
+    // The rewards vault is owned by the state PDA
+    let state_bump = [ctx.accounts.state.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"state", &state_bump]];
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.rewards_vault.to_account_info(),
+        to: ctx.accounts.user_token_account.to_account_info(),
+        authority: ctx.accounts.state.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)?;
+
+    let state = &mut ctx.accounts.state;
+    let user_rewards = &mut ctx.accounts.user_rewards;
+    let now = Clock::get()?.unix_timestamp;
+
+    user_rewards.accrued = 0;
+    user_rewards.total_claimed = user_rewards
+        .total_claimed
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    user_rewards.last_claimed_at = now;
+    user_rewards.last_updated = now;
+
+    emit!(RewardsClaimed {
+        sequence: state.next_event_sequence()?,
+        user: user_rewards.user,
+        user_rewards: user_rewards.key(),
+        mint: user_rewards.mint,
+        recipient: ctx.accounts.user_token_account.key(),
+        amount,
+        timestamp: now,
+    });
+
+    msg!("Claimed {} reward tokens", amount);
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use crate::error::ErrorCode;
+use crate::events::{AdClosed, BudgetRefunded, ReputationUpdated};
+use crate::reputation::ReputationReason;
+use crate::state::{
+    AdAccount, AdvertiserAccount, Campaign, PaymentMintRegistry, ProtocolConfig, StateAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct CloseAd<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Required for an ad paid in a mint other than the primary one
+    #[account(seeds = [b"payment_mint_registry"], bump = payment_mint_registry.bump)]
+    pub payment_mint_registry: Option<Account<'info, PaymentMintRegistry>>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = advertiser @ ErrorCode::Unauthorized,
+        constraint = ad.has_vault @ ErrorCode::AdVaultNotMigrated,
+    )]
+    pub ad: Account<'info, AdAccount>,
+
+    /// The ad's own vault, or its campaign's vault for a campaign ad, which
+    /// is left untouched
+    #[account(mut, address = ad.budget_vault(&ad.key())?)]
+    pub ad_vault: Account<'info, TokenAccount>,
+
+    /// Required for campaign ads
+    #[account(mut, address = ad.campaign @ ErrorCode::CampaignMismatch)]
+    pub campaign: Option<Account<'info, Campaign>>,
+
+    #[account(
+        mut,
+        constraint = advertiser_token_account.owner == authority.key(),
+        constraint = advertiser_token_account.mint == ad.mint,
+    )]
+    pub advertiser_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Closes the ad, refunds its unspent budget and scores its outcome into the
+/// advertiser's reputation. A campaign ad has no budget of its own: the
+/// campaign's budget and outcome are settled by `close_campaign`.
+pub fn handler(ctx: Context<CloseAd>) -> Result<()> {
+    let is_campaign_ad = ctx.accounts.ad.is_campaign_ad();
+    require!(
+        is_campaign_ad == ctx.accounts.campaign.is_some(),
+        ErrorCode::CampaignMismatch
+    );
+    if let Some(campaign) = ctx.accounts.campaign.as_mut() {
+        campaign.open_ad_count = campaign
+            .open_ad_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::Overflow)?;
+        campaign.last_updated = Clock::get()?.unix_timestamp;
+    }
+
+    let unspent = ctx.accounts.ad.remaining_budget()?;
+    // Anything sent to the vault directly is swept back along with the budget
+    let refund = if is_campaign_ad {
+        0
+    } else {
+        ctx.accounts.ad_vault.amount
+    };
+
+    // The vault is owned by the state PDA
+    let state_bump = [ctx.accounts.state.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"state", &state_bump]];
+
+    if refund > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.ad_vault.to_account_info(),
+            to: ctx.accounts.advertiser_token_account.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token::transfer(cpi_ctx, refund)?;
+    }
+
+    // Reclaim the vault's rent along with the ad's
+    if !is_campaign_ad {
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.ad_vault.to_account_info(),
+            destination: ctx.accounts.authority.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token::close_account(cpi_ctx)?;
+    }
+
+    // The outcome is scored in the mint the budget was paid in
+    let params = ctx.accounts.state.mint_params(
+        &ctx.accounts.ad.mint,
+        &ctx.accounts.protocol_config.params,
+        ctx.accounts.payment_mint_registry.as_deref(),
+    )?;
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+    let ad = &ctx.accounts.ad;
+    let now = Clock::get()?.unix_timestamp;
+
+    // Ads closed while waiting for review never ran, so they do not count;
+    // campaign ads count towards their campaign
+    advertiser.refresh_reputation(now, params.reputation_half_life);
+    let outcome = if ad.pending_review || is_campaign_ad {
+        0
+    } else {
+        let refunded = ad
+            .refunded
+            .checked_add(unspent)
+            .ok_or(ErrorCode::Overflow)?;
+        params.campaign_outcome(ad.budget, ad.spent_budget, refunded)
+    };
+    advertiser.apply_campaign_outcome(outcome);
+
+    // Budget totals count deposits net of refunds
+    advertiser.total_budget = advertiser
+        .total_budget
+        .checked_sub(unspent)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.open_ad_count = advertiser
+        .open_ad_count
+        .checked_sub(1)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.last_updated = now;
+    state.total_budget = state
+        .total_budget
+        .checked_sub(unspent)
+        .ok_or(ErrorCode::Overflow)?;
+    state.last_updated = now;
+
+    if refund > 0 {
+        emit!(BudgetRefunded {
+            sequence: state.next_event_sequence()?,
+            ad: ad.key(),
+            advertiser: advertiser.key(),
+            recipient: ctx.accounts.advertiser_token_account.key(),
+            amount: refund,
+            timestamp: now,
+        });
+    }
+    emit!(AdClosed {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: advertiser.key(),
+        spent_budget: ad.spent_budget,
+        timestamp: now,
+    });
+    if outcome != 0 {
+        emit!(ReputationUpdated {
+            sequence: state.next_event_sequence()?,
+            advertiser: advertiser.key(),
+            reason: ReputationReason::CampaignClosed,
+            reputation_score: advertiser.reputation_score,
+            timestamp: now,
+        });
+    }
+
+    msg!("Ad closed, refunded {} tokens", refund);
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use crate::error::ErrorCode;
+use crate::events::AdvertiserClosed;
+use crate::state::{AdvertiserAccount, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CloseAdvertiser<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = advertiser.open_ad_count == 0 @ ErrorCode::AdvertiserHasOpenAds,
+        constraint = advertiser.open_campaign_count == 0 @ ErrorCode::AdvertiserHasOpenAds,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    /// CHECK: the advertiser's stake PDA, which must already be emptied by
+    /// `withdraw_stake`; that is only possible once the advertiser is deactivated
+    #[account(
+        seeds = [b"advertiser_stake", advertiser.key().as_ref()],
+        bump,
+        constraint = stake.data_is_empty() @ ErrorCode::StakeLocked,
+    )]
+    pub stake: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<CloseAdvertiser>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let advertiser = &ctx.accounts.advertiser;
+    let now = Clock::get()?.unix_timestamp;
+
+    state.advertiser_count = state
+        .advertiser_count
+        .checked_sub(1)
+        .ok_or(ErrorCode::Overflow)?;
+    state.last_updated = now;
+
+    emit!(AdvertiserClosed {
+        sequence: state.next_event_sequence()?,
+        advertiser: advertiser.key(),
+        authority: advertiser.authority,
+        timestamp: now,
+    });
+
+    msg!("Advertiser closed: {}", advertiser.key());
+    Ok(())
+}
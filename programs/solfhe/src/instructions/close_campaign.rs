@@ -0,0 +1,149 @@
+use crate::error::ErrorCode;
+use crate::events::{CampaignClosed, ReputationUpdated};
+use crate::reputation::ReputationReason;
+use crate::state::{
+    AdvertiserAccount, Campaign, PaymentMintRegistry, ProtocolConfig, StateAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct CloseCampaign<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Required for a campaign paid in a mint other than the primary one
+    #[account(seeds = [b"payment_mint_registry"], bump = payment_mint_registry.bump)]
+    pub payment_mint_registry: Option<Account<'info, PaymentMintRegistry>>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = advertiser @ ErrorCode::Unauthorized,
+        constraint = campaign.open_ad_count == 0 @ ErrorCode::CampaignHasOpenAds,
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump = campaign.vault_bump,
+    )]
+    pub campaign_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = advertiser_token_account.owner == authority.key(),
+        constraint = advertiser_token_account.mint == campaign.mint,
+    )]
+    pub advertiser_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Closes a campaign once all of its ads are closed, refunds the unspent
+/// shared budget and scores the campaign's outcome into the advertiser's
+/// reputation.
+pub fn handler(ctx: Context<CloseCampaign>) -> Result<()> {
+    let unspent = ctx.accounts.campaign.remaining_budget()?;
+    // Anything sent to the vault directly is swept back along with the budget
+    let refund = ctx.accounts.campaign_vault.amount;
+
+    // The vault is owned by the state PDA
+    let state_bump = [ctx.accounts.state.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"state", &state_bump]];
+
+    if refund > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.campaign_vault.to_account_info(),
+            to: ctx.accounts.advertiser_token_account.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token::transfer(cpi_ctx, refund)?;
+    }
+
+    let cpi_accounts = CloseAccount {
+        account: ctx.accounts.campaign_vault.to_account_info(),
+        destination: ctx.accounts.authority.to_account_info(),
+        authority: ctx.accounts.state.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::close_account(cpi_ctx)?;
+
+    // The outcome is scored in the mint the budget was paid in
+    let params = ctx.accounts.state.mint_params(
+        &ctx.accounts.campaign.mint,
+        &ctx.accounts.protocol_config.params,
+        ctx.accounts.payment_mint_registry.as_deref(),
+    )?;
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+    let campaign = &ctx.accounts.campaign;
+    let now = Clock::get()?.unix_timestamp;
+
+    advertiser.refresh_reputation(now, params.reputation_half_life);
+    let outcome = params.campaign_outcome(campaign.budget, campaign.spent_budget, unspent);
+    advertiser.apply_campaign_outcome(outcome);
+
+    // Budget totals count deposits net of refunds
+    advertiser.total_budget = advertiser
+        .total_budget
+        .checked_sub(unspent)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.open_campaign_count = advertiser
+        .open_campaign_count
+        .checked_sub(1)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.last_updated = now;
+    state.total_budget = state
+        .total_budget
+        .checked_sub(unspent)
+        .ok_or(ErrorCode::Overflow)?;
+    state.last_updated = now;
+
+    emit!(CampaignClosed {
+        sequence: state.next_event_sequence()?,
+        campaign: campaign.key(),
+        advertiser: advertiser.key(),
+        spent_budget: campaign.spent_budget,
+        impressions: campaign.impressions,
+        clicks: campaign.clicks,
+        recipient: ctx.accounts.advertiser_token_account.key(),
+        refund,
+        timestamp: now,
+    });
+    if outcome != 0 {
+        emit!(ReputationUpdated {
+            sequence: state.next_event_sequence()?,
+            advertiser: advertiser.key(),
+            reason: ReputationReason::CampaignClosed,
+            reputation_score: advertiser.reputation_score,
+            timestamp: now,
+        });
+    }
+
+    msg!("Campaign closed, refunded {} tokens", refund);
+    Ok(())
+}
@@ -1,26 +1,41 @@
+use crate::envelope::CiphertextEnvelope;
 use crate::error::ErrorCode;
-use crate::events::AdCreated;
-use crate::state::{AdAccount, AdvertiserAccount, StateAccount};
+use crate::events::{AdCreated, ProtocolFeeCollected};
+use crate::reputation::ReputationTier;
+use crate::state::{
+    AdAccount, AdvertiserAccount, FheKeyRegistry, Pacing, PaymentMintRegistry, ProtocolConfig,
+    StateAccount, MAX_ENCRYPTED_TRAITS_SIZE,
+};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
-use tfhe::prelude::*;
-use tfhe::shortint::parameters::PARAM_MESSAGE_2_CARRY_2;
-use tfhe::shortint::prelude::*;
-
-// Constants
-const MAX_CONTENT_LENGTH: usize = 1000;
-const MIN_AD_DURATION: i64 = 60 * 60; // 1 hour
-const MAX_AD_DURATION: i64 = 30 * 24 * 60 * 60; // 30 days
-const MIN_AD_BUDGET: u64 = 100_000_000; // 0.1 SOL
-const FHE_TRAITS_COUNT: usize = 5; // Number of encrypted traits
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 #[derive(Accounts)]
-#[instruction(content: String, encrypted_target_traits: Vec<u8>, duration: i64, budget: u64)]
+#[instruction(content: String, encrypted_target_traits: CiphertextEnvelope, duration: i64, budget: u64, daily_cap: u64, pacing: Pacing, start_at: Option<i64>)]
 pub struct CreateAd<'info> {
-    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
     pub state: Account<'info, StateAccount>,
 
-    #[account(mut, seeds = [b"advertiser", authority.key().as_ref()], bump)]
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"fhe_key_registry"], bump = fhe_key_registry.bump)]
+    pub fhe_key_registry: Account<'info, FheKeyRegistry>,
+
+    /// Required when paying with a mint other than the primary payment mint
+    #[account(seeds = [b"payment_mint_registry"], bump = payment_mint_registry.bump)]
+    pub payment_mint_registry: Option<Account<'info, PaymentMintRegistry>>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        constraint = advertiser.is_active @ ErrorCode::AdvertiserNotActive,
+    )]
     pub advertiser: Account<'info, AdvertiserAccount>,
 
     #[account(
@@ -35,12 +50,30 @@ pub struct CreateAd<'info> {
     #[account(
         mut,
         constraint = advertiser_token_account.owner == authority.key(),
-        constraint = advertiser_token_account.mint == state.payment_mint,
+        constraint = advertiser_token_account.mint == payment_mint.key(),
     )]
     pub advertiser_token_account: Account<'info, TokenAccount>,
 
-    #[account(mut, constraint = treasury.mint == state.payment_mint, seeds = [b"treasury"], bump)]
-    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"ad_vault", ad.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = state,
+    )]
+    pub ad_vault: Account<'info, TokenAccount>,
+
+    /// The primary payment mint, or one accepted by the registry
+    pub payment_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.mint == payment_mint.key(),
+        seeds = [b"fee_vault", state.mint_seed(&payment_mint.key())],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
 
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -49,31 +82,54 @@ pub struct CreateAd<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<CreateAd>,
     content: String,
-    encrypted_target_traits: Vec<u8>,
+    encrypted_target_traits: CiphertextEnvelope,
     duration: i64,
     budget: u64,
+    daily_cap: u64,
+    pacing: Pacing,
+    start_at: Option<i64>,
 ) -> Result<()> {
-    // Validate input data
+    // Validate input data against the protocol parameters, with amounts in
+    // the mint the budget is paid in
+    let mint = ctx.accounts.payment_mint.key();
+    let params = ctx.accounts.state.deposit_params(
+        &mint,
+        &ctx.accounts.protocol_config.params,
+        ctx.accounts.payment_mint_registry.as_deref(),
+    )?;
+    params.check_content(&content)?;
+    params.check_duration(duration)?;
     require!(
-        !content.is_empty() && content.len() <= MAX_CONTENT_LENGTH,
-        ErrorCode::InvalidAdContent
+        budget >= params.min_ad_budget,
+        ErrorCode::InsufficientAdBudget
     );
+
+    // Ads start right away unless scheduled, at most one maximum run time ahead
+    let now = Clock::get()?.unix_timestamp;
+    let start_at = start_at.unwrap_or(now);
     require!(
-        duration >= MIN_AD_DURATION && duration <= MAX_AD_DURATION,
-        ErrorCode::InvalidAdDuration
+        start_at >= now && start_at - now <= params.max_ad_duration,
+        ErrorCode::InvalidStartTime
     );
-    require!(budget >= MIN_AD_BUDGET, ErrorCode::InsufficientAdBudget);
 
     let state = &mut ctx.accounts.state;
     let advertiser = &mut ctx.accounts.advertiser;
     let ad = &mut ctx.accounts.ad;
     let authority = &ctx.accounts.authority;
 
-    // Verify and process FHE encrypted data
-    let processed_traits = process_fhe_traits(&encrypted_target_traits)?;
+    // Low-reputation advertisers get capped budgets, and the lowest need review
+    advertiser.refresh_reputation(now, params.reputation_half_life);
+    params.check_budget_cap(advertiser.reputation_score, budget)?;
+    let pending_review =
+        params.reputation_tier(advertiser.reputation_score) == ReputationTier::Review;
+
+    // Check the FHE envelope against the network keys; evaluation happens in the coprocessor
+    let stored_traits = encrypted_target_traits
+        .validate(&ctx.accounts.fhe_key_registry, MAX_ENCRYPTED_TRAITS_SIZE)?;
 
     // Check if advertiser has enough balance
     require!(
@@ -81,37 +137,74 @@ pub fn handler(
         ErrorCode::InsufficientFunds
     );
 
-    // Transfer tokens from advertiser to treasury
+    // The protocol fee comes out of the deposit; the rest is the ad's budget
+    let fee = state.collect_fee(budget)?;
+    let net_budget = budget - fee;
+
+    // A daily cap must cover at least one settlement and fit the budget
+    let rates = state.settlement_rates(&mint, ctx.accounts.payment_mint_registry.as_deref())?;
+    AdAccount::validate_daily_cap(daily_cap, net_budget, rates)?;
+
+    if fee > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.advertiser_token_account.to_account_info(),
+            to: ctx.accounts.fee_vault.to_account_info(),
+            authority: authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, fee)?;
+    }
+
+    // Escrow the budget in the ad's own vault
     let cpi_accounts = Transfer {
         from: ctx.accounts.advertiser_token_account.to_account_info(),
-        to: ctx.accounts.treasury.to_account_info(),
+        to: ctx.accounts.ad_vault.to_account_info(),
         authority: authority.to_account_info(),
     };
     let cpi_program = ctx.accounts.token_program.to_account_info();
     let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-    token::transfer(cpi_ctx, budget)?;
+    token::transfer(cpi_ctx, net_budget)?;
 
     // Initialize the ad account
     ad.advertiser = advertiser.key();
     ad.content = content.clone();
-    ad.encrypted_target_traits = processed_traits;
+    ad.encrypted_target_traits = stored_traits;
+    ad.key_epoch = encrypted_target_traits.key_epoch;
+    ad.trait_schema = encrypted_target_traits.trait_schema;
     ad.duration = duration;
-    ad.budget = budget;
+    ad.budget = net_budget;
     ad.spent_budget = 0;
     ad.impressions = 0;
     ad.clicks = 0;
-    ad.is_active = true;
-    ad.created_at = Clock::get()?.unix_timestamp;
+    ad.is_active = !pending_review;
+    ad.pending_review = pending_review;
+    ad.campaign = Pubkey::default();
+    ad.daily_cap = daily_cap;
+    ad.pacing = pacing;
+    ad.spend_day = 0;
+    ad.day_spent = 0;
+    ad.start_at = start_at;
+    ad.refunded = 0;
+    ad.mint = mint;
+    ad.deposited = budget;
+    ad.created_at = now;
     ad.last_updated = ad.created_at;
+    ad.has_vault = true;
+    ad.vault_bump = ctx.bumps.ad_vault;
 
     // Update advertiser account
     advertiser.ad_count = advertiser
         .ad_count
         .checked_add(1)
         .ok_or(ErrorCode::Overflow)?;
+    advertiser.open_ad_count = advertiser
+        .open_ad_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
     advertiser.total_budget = advertiser
         .total_budget
-        .checked_add(budget)
+        .checked_add(net_budget)
         .ok_or(ErrorCode::Overflow)?;
     advertiser.last_updated = Clock::get()?.unix_timestamp;
 
@@ -119,17 +212,36 @@ pub fn handler(
     state.ad_count = state.ad_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
     state.total_budget = state
         .total_budget
-        .checked_add(budget)
+        .checked_add(net_budget)
         .ok_or(ErrorCode::Overflow)?;
     state.last_updated = Clock::get()?.unix_timestamp;
 
+    if fee > 0 {
+        emit!(ProtocolFeeCollected {
+            sequence: state.next_event_sequence()?,
+            ad: ad.key(),
+            campaign: None,
+            amount: fee,
+            timestamp: ad.created_at,
+        });
+    }
+
     // Emit an event for ad creation
     emit!(AdCreated {
+        sequence: state.next_event_sequence()?,
         ad: ad.key(),
         advertiser: advertiser.key(),
         content: content.clone(),
-        budget,
+        key_epoch: ad.key_epoch,
+        trait_schema: ad.trait_schema,
+        budget: net_budget,
         duration,
+        start_at,
+        mint,
+        pending_review,
+        daily_cap,
+        pacing,
+        campaign: Pubkey::default(),
         created_at: ad.created_at,
     });
 
@@ -137,268 +249,188 @@ pub fn handler(
     Ok(())
 }
 
-// Function to process FHE encrypted traits
-fn process_fhe_traits(encrypted_data: &[u8]) -> Result<Vec<u8>> {
-    // Deserialize the encrypted data
-    let ciphertexts: Vec<Ciphertext> =
-        bincode::deserialize(encrypted_data).map_err(|_| ErrorCode::InvalidFheEncryption)?;
-
-    // Ensure we have the correct number of traits
-    require!(
-        ciphertexts.len() == FHE_TRAITS_COUNT,
-        ErrorCode::InvalidTargetTraits
-    );
-
-    // Generate a new server key for FHE operations
-    let client_key = ClientKey::new(PARAM_MESSAGE_2_CARRY_2);
-    let server_key = ServerKey::new(&client_key);
-
-    // Process each trait (for example, adding 1 to each trait)
-    let processed_ciphertexts: Vec<Ciphertext> = ciphertexts
-        .into_iter()
-        .map(|ct| server_key.scalar_add(&ct, 1))
-        .collect();
-
-    // Serialize the processed ciphertexts
-    bincode::serialize(&processed_ciphertexts).map_err(|_| ErrorCode::SerializationError.into())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::envelope::{FheScheme, FHE_TRAITS_COUNT, TRAIT_SCHEMA_V1};
+    use crate::state::{ProtocolParams, RESERVED_SPACE};
+    use crate::test_utils::{install_syscalls, read_account, MockAccount, TEST_TIMESTAMP};
     use anchor_lang::solana_program::pubkey::Pubkey;
+    use tfhe::shortint::parameters::PARAM_MESSAGE_2_CARRY_2;
     use tfhe::shortint::prelude::*;
 
-    fn setup_test_environment() -> (Pubkey, Pubkey, Pubkey, Pubkey, Pubkey) {
-        let program_id = Pubkey::new_unique();
+    struct Fixture {
+        state: MockAccount,
+        protocol_config: MockAccount,
+        fhe_key_registry: MockAccount,
+        advertiser: MockAccount,
+        ad: MockAccount,
+        advertiser_token_account: MockAccount,
+        ad_vault: MockAccount,
+        payment_mint: MockAccount,
+        fee_vault: MockAccount,
+        authority: MockAccount,
+        token_program: MockAccount,
+        system_program: MockAccount,
+    }
+
+    fn create_mock_accounts() -> Fixture {
+        let program_id = crate::ID;
         let authority_pubkey = Pubkey::new_unique();
+        let payment_mint = Pubkey::new_unique();
         let (state_pubkey, _) = Pubkey::find_program_address(&[b"state"], &program_id);
         let (advertiser_pubkey, _) =
             Pubkey::find_program_address(&[b"advertiser", authority_pubkey.as_ref()], &program_id);
         let (ad_pubkey, _) = Pubkey::find_program_address(
-            &[b"ad", advertiser_pubkey.as_ref(), &[0, 0, 0, 0, 0, 0, 0, 0]],
+            &[b"ad", advertiser_pubkey.as_ref(), &0u64.to_le_bytes()],
             &program_id,
         );
-        let (treasury_pubkey, _) = Pubkey::find_program_address(&[b"treasury"], &program_id);
-
-        (
-            program_id,
-            authority_pubkey,
-            state_pubkey,
-            advertiser_pubkey,
-            ad_pubkey,
-        )
-    }
+        let (ad_vault_pubkey, _) =
+            Pubkey::find_program_address(&[b"ad_vault", ad_pubkey.as_ref()], &program_id);
+        // Fee vault of the primary payment mint, derived without the mint
+        let (fee_vault_pubkey, _) = Pubkey::find_program_address(&[b"fee_vault"], &program_id);
 
-    fn create_mock_accounts(
-        program_id: &Pubkey,
-        authority_pubkey: &Pubkey,
-        state_pubkey: &Pubkey,
-        advertiser_pubkey: &Pubkey,
-        ad_pubkey: &Pubkey,
-    ) -> (
-        AccountInfo,
-        AccountInfo,
-        AccountInfo,
-        AccountInfo,
-        AccountInfo,
-        AccountInfo,
-        AccountInfo,
-        AccountInfo,
-    ) {
-        // Create mock state account
-        let mut state_account = StateAccount {
+        let state_account = StateAccount {
             bump: 255,
-            authority: *authority_pubkey,
+            authority: authority_pubkey,
+            coprocessor: Pubkey::new_unique(),
             advertiser_count: 1,
             user_count: 0,
             ad_count: 0,
             total_budget: 0,
-            payment_mint: Pubkey::new_unique(),
+            cpm_rate: 0,
+            cpc_rate: 0,
+            user_reward_bps: 0,
+            protocol_fee_bps: 0,
+            total_fees_collected: 0,
+            pending_authority: Pubkey::default(),
+            is_paused: false,
+            payment_mint,
             last_updated: 0,
+            event_sequence: 4,
+            reserved: [0; RESERVED_SPACE],
+        };
+
+        // FHE key registry at epoch 0
+        let fhe_key_registry_account = FheKeyRegistry {
+            parameter_set: 1,
+            bump: 254,
+            ..FheKeyRegistry::default()
+        };
+
+        // Protocol config with the default parameters
+        let protocol_config_account = ProtocolConfig {
+            params: ProtocolParams::default(),
+            bump: 253,
+            ..ProtocolConfig::default()
         };
-        let mut state_data = state_account.try_to_vec().unwrap();
-        let mut state_lamports = 1000000000;
-        let state_account_info = AccountInfo::new(
-            state_pubkey,
-            false,
-            true,
-            &mut state_lamports,
-            &mut state_data,
-            program_id,
-            false,
-            0,
-        );
 
-        // Create mock advertiser account
-        let mut advertiser_account = AdvertiserAccount {
-            authority: *authority_pubkey,
+        let advertiser_account = AdvertiserAccount {
+            authority: authority_pubkey,
             name: "Test Advertiser".to_string(),
-            email: "test@example.com".to_string(),
+            email_commitment: [1; 32],
             ad_count: 0,
             total_budget: 0,
             reputation_score: 100,
             is_active: true,
             created_at: 0,
             last_updated: 0,
+            bump: 255,
+            open_ad_count: 0,
+            email_verified_at: 0,
+            reputation_updated_at: TEST_TIMESTAMP,
+            campaign_count: 0,
+            open_campaign_count: 0,
+            reserved: [0; RESERVED_SPACE],
         };
-        let mut advertiser_data = advertiser_account.try_to_vec().unwrap();
-        let mut advertiser_lamports = 1000000000;
-        let advertiser_account_info = AccountInfo::new(
-            advertiser_pubkey,
-            false,
-            true,
-            &mut advertiser_lamports,
-            &mut advertiser_data,
-            program_id,
-            false,
-            0,
-        );
-
-        // Create mock ad account
-        let mut ad_data = vec![0; AdAccount::SPACE];
-        let mut ad_lamports = 0;
-        let ad_account_info = AccountInfo::new(
-            ad_pubkey,
-            false,
-            true,
-            &mut ad_lamports,
-            &mut ad_data,
-            program_id,
-            false,
-            0,
-        );
-
-        // Create mock token accounts
-        let mut advertiser_token_account = TokenAccount::default();
-        advertiser_token_account.owner = *authority_pubkey;
-        advertiser_token_account.mint = state_account.payment_mint;
-        advertiser_token_account.amount = 1000000000;
-        let mut advertiser_token_data = advertiser_token_account.try_to_vec().unwrap();
-        let mut advertiser_token_lamports = 1000000000;
-        let advertiser_token_account_info = AccountInfo::new(
-            &Pubkey::new_unique(),
-            false,
-            true,
-            &mut advertiser_token_lamports,
-            &mut advertiser_token_data,
-            program_id,
-            false,
-            0,
-        );
-
-        let mut treasury_account = TokenAccount::default();
-        treasury_account.owner = *state_pubkey;
-        treasury_account.mint = state_account.payment_mint;
-        let mut treasury_data = treasury_account.try_to_vec().unwrap();
-        let mut treasury_lamports = 1000000000;
-        let treasury_account_info = AccountInfo::new(
-            &Pubkey::new_unique(),
-            false,
-            true,
-            &mut treasury_lamports,
-            &mut treasury_data,
-            program_id,
-            false,
-            0,
-        );
-
-        let mut authority_lamports = 1000000000;
-        let mut authority_data = vec![];
-        let authority_account_info = AccountInfo::new(
-            authority_pubkey,
-            true,
-            false,
-            &mut authority_lamports,
-            &mut authority_data,
-            &Pubkey::default(),
-            false,
-            0,
-        );
-
-        let token_program_id = Pubkey::new_unique();
-        let mut token_program_lamports = 0;
-        let mut token_program_data = vec![];
-        let token_program_account_info = AccountInfo::new(
-            &token_program_id,
-            false,
-            false,
-            &mut token_program_lamports,
-            &mut token_program_data,
-            &Pubkey::default(),
-            false,
-            0,
-        );
-
-        let system_program_id = Pubkey::new_unique();
-        let mut system_program_lamports = 0;
-        let mut system_program_data = vec![];
-        let system_program_account_info = AccountInfo::new(
-            &system_program_id,
-            false,
-            false,
-            &mut system_program_lamports,
-            &mut system_program_data,
-            &Pubkey::default(),
-            false,
-            0,
-        );
 
-        (
-            state_account_info,
-            advertiser_account_info,
-            ad_account_info,
-            advertiser_token_account_info,
-            treasury_account_info,
-            authority_account_info,
-            token_program_account_info,
-            system_program_account_info,
-        )
+        Fixture {
+            state: MockAccount::program_account(state_pubkey, &state_account, StateAccount::SPACE),
+            protocol_config: MockAccount::program_account(
+                Pubkey::find_program_address(&[b"protocol_config"], &program_id).0,
+                &protocol_config_account,
+                ProtocolConfig::SPACE,
+            ),
+            fhe_key_registry: MockAccount::program_account(
+                Pubkey::find_program_address(&[b"fhe_key_registry"], &program_id).0,
+                &fhe_key_registry_account,
+                FheKeyRegistry::SPACE,
+            ),
+            advertiser: MockAccount::program_account(
+                advertiser_pubkey,
+                &advertiser_account,
+                AdvertiserAccount::SPACE,
+            ),
+            ad: MockAccount::init(ad_pubkey, AdAccount::SPACE),
+            advertiser_token_account: MockAccount::token_account(
+                Pubkey::new_unique(),
+                payment_mint,
+                authority_pubkey,
+                1_000_000_000,
+            ),
+            ad_vault: MockAccount::token_account(ad_vault_pubkey, payment_mint, state_pubkey, 0),
+            payment_mint: MockAccount::mint(payment_mint),
+            fee_vault: MockAccount::token_account(fee_vault_pubkey, payment_mint, state_pubkey, 0),
+            authority: MockAccount::signer(authority_pubkey),
+            token_program: MockAccount::program(token::ID),
+            system_program: MockAccount::program(anchor_lang::system_program::ID),
+        }
     }
 
     #[test]
     fn test_create_ad_with_realistic_fhe() {
-        let (program_id, authority_pubkey, state_pubkey, advertiser_pubkey, ad_pubkey) =
-            setup_test_environment();
-
-        let (
-            state_account_info,
-            advertiser_account_info,
-            ad_account_info,
-            advertiser_token_account_info,
-            treasury_account_info,
-            authority_account_info,
-            token_program_account_info,
-            system_program_account_info,
-        ) = create_mock_accounts(
-            &program_id,
-            &authority_pubkey,
-            &state_pubkey,
-            &advertiser_pubkey,
-            &ad_pubkey,
-        );
+        install_syscalls();
+        let program_id = crate::ID;
+        let mut fixture = create_mock_accounts();
+        let state_account_info = fixture.state.info();
+        let protocol_config_account_info = fixture.protocol_config.info();
+        let fhe_key_registry_account_info = fixture.fhe_key_registry.info();
+        let advertiser_account_info = fixture.advertiser.info();
+        let ad_account_info = fixture.ad.info();
+        let advertiser_token_account_info = fixture.advertiser_token_account.info();
+        let ad_vault_account_info = fixture.ad_vault.info();
+        let payment_mint_account_info = fixture.payment_mint.info();
+        let fee_vault_account_info = fixture.fee_vault.info();
+        let authority_account_info = fixture.authority.info();
+        let token_program_account_info = fixture.token_program.info();
+        let system_program_account_info = fixture.system_program.info();
 
         let client_key = ClientKey::new(PARAM_MESSAGE_2_CARRY_2);
-        let server_key = ServerKey::new(&client_key);
 
-        // Realistic target traits (i prefered age)
-        let target_traits: Vec<u64> = vec![25, 30, 35, 40, 45];
+        // Target bucket of each schema v1 trait; these parameters carry
+        // 2-bit messages
+        let target_traits: Vec<u64> = vec![1, 3, 0, 2, 1];
 
-        // Encry target traits
-        let encrypted_traits: Vec<Ciphertext> = target_traits
+        // Encrypt target traits
+        let encrypted_traits: Vec<CiphertextBig> = target_traits
             .iter()
             .map(|&trait_value| client_key.encrypt(trait_value))
             .collect();
 
-        // Serialise encrypted properties
-        let encrypted_target_traits = bincode::serialize(&encrypted_traits).unwrap();
+        // Serialise encrypted properties, as uploaded off-chain, and describe
+        // them in an envelope for the current key epoch
+        let payload = bincode::serialize(&encrypted_traits).unwrap();
+        assert!(payload.len() > MAX_ENCRYPTED_TRAITS_SIZE);
+        let encrypted_target_traits = CiphertextEnvelope::new(
+            FheScheme::TfheShortint,
+            1,
+            0,
+            TRAIT_SCHEMA_V1,
+            "https://ciphertexts.solfhe.xyz/ad-traits".to_string(),
+            &payload,
+        );
 
-        let accounts = CreateAd {
+        let mut accounts = CreateAd {
             state: Account::try_from(&state_account_info).unwrap(),
+            protocol_config: Account::try_from(&protocol_config_account_info).unwrap(),
+            fhe_key_registry: Account::try_from(&fhe_key_registry_account_info).unwrap(),
+            payment_mint_registry: None,
             advertiser: Account::try_from(&advertiser_account_info).unwrap(),
-            ad: Account::try_from(&ad_account_info).unwrap(),
+            ad: Account::try_from_unchecked(&ad_account_info).unwrap(),
             advertiser_token_account: Account::try_from(&advertiser_token_account_info).unwrap(),
-            treasury: Account::try_from(&treasury_account_info).unwrap(),
+            ad_vault: Account::try_from(&ad_vault_account_info).unwrap(),
+            payment_mint: Account::try_from(&payment_mint_account_info).unwrap(),
+            fee_vault: Account::try_from(&fee_vault_account_info).unwrap(),
             authority: Signer::try_from(&authority_account_info).unwrap(),
             token_program: Program::try_from(&token_program_account_info).unwrap(),
             system_program: Program::try_from(&system_program_account_info).unwrap(),
@@ -408,53 +440,60 @@ mod tests {
         let duration = 24 * 60 * 60; // 1 day
         let budget = 500_000_000; // 0.5 SOL
 
-        let mut context = Context::new(
-            program_id,
-            accounts,
-            &[
-                &state_account_info,
-                &advertiser_account_info,
-                &ad_account_info,
-                &advertiser_token_account_info,
-                &treasury_account_info,
-                &authority_account_info,
-                &token_program_account_info,
-                &system_program_account_info,
-            ],
-            BTreeMap::new(),
-            BTreeMap::new(),
+        let context = Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            CreateAdBumps {
+                ad_vault: 253,
+                ..CreateAdBumps::default()
+            },
         );
 
         let result = handler(
             context,
             content.clone(),
-            encrypted_target_traits,
+            encrypted_target_traits.clone(),
             duration,
             budget,
+            0,
+            Pacing::Even,
+            None,
         );
         assert!(result.is_ok());
-
-        // Verify name account
-        let ad = AdAccount::try_from_slice(&ad_account_info.data.borrow()).unwrap();
-        let processed_traits: Vec<Ciphertext> =
-            bincode::deserialize(&ad.encrypted_target_traits).unwrap();
-        assert_eq!(processed_traits.len(), FHE_TRAITS_COUNT);
-
-        // Solve and verify processed properties
-        for (i, ct) in processed_traits.iter().enumerate() {
-            let decrypted = client_key.decrypt(ct);
-            assert_eq!(decrypted, target_traits[i] + 1); // Orijinal değer + 1
+        accounts.exit(&program_id).unwrap();
+
+        // The envelope is stored untouched and still pins the uploaded ciphertexts
+        let ad: AdAccount = read_account(&ad_account_info);
+        let stored_envelope = CiphertextEnvelope::from_bytes(&ad.encrypted_target_traits).unwrap();
+        assert_eq!(stored_envelope, encrypted_target_traits);
+        stored_envelope.verify_payload(&payload).unwrap();
+        assert_eq!(ad.trait_schema, TRAIT_SCHEMA_V1);
+        assert!(ad.has_vault);
+        assert_eq!(ad.vault_bump, 253);
+        assert!(ad.is_active);
+        assert!(!ad.pending_review);
+        assert_eq!(ad.pacing, Pacing::Even);
+        assert_eq!(ad.created_at, TEST_TIMESTAMP);
+        assert_eq!(ad.start_at, ad.created_at);
+        assert_eq!(ad.mint, *payment_mint_account_info.key);
+        assert_eq!(ad.deposited, budget);
+        let stored_traits: Vec<CiphertextBig> = bincode::deserialize(&payload).unwrap();
+        assert_eq!(stored_traits.len(), FHE_TRAITS_COUNT);
+
+        for (i, ct) in stored_traits.iter().enumerate() {
+            assert_eq!(client_key.decrypt(ct), target_traits[i]);
         }
 
         // Verify other account updates
-        let updated_state =
-            StateAccount::try_from_slice(&state_account_info.data.borrow()).unwrap();
+        let updated_state: StateAccount = read_account(&state_account_info);
         assert_eq!(updated_state.ad_count, 1);
         assert_eq!(updated_state.total_budget, budget);
+        assert_eq!(updated_state.event_sequence, 5);
 
-        let updated_advertiser =
-            AdvertiserAccount::try_from_slice(&advertiser_account_info.data.borrow()).unwrap();
+        let updated_advertiser: AdvertiserAccount = read_account(&advertiser_account_info);
         assert_eq!(updated_advertiser.ad_count, 1);
+        assert_eq!(updated_advertiser.open_ad_count, 1);
         assert_eq!(updated_advertiser.total_budget, budget);
     }
 }
@@ -0,0 +1,217 @@
+use crate::envelope::CiphertextEnvelope;
+use crate::error::ErrorCode;
+use crate::events::{CampaignCreated, ProtocolFeeCollected};
+use crate::state::{
+    AdvertiserAccount, Campaign, FheKeyRegistry, PaymentMintRegistry, ProtocolConfig, StateAccount,
+    MAX_ENCRYPTED_TRAITS_SIZE,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct CreateCampaign<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"fhe_key_registry"], bump = fhe_key_registry.bump)]
+    pub fhe_key_registry: Account<'info, FheKeyRegistry>,
+
+    /// Required when paying with a mint other than the primary payment mint
+    #[account(seeds = [b"payment_mint_registry"], bump = payment_mint_registry.bump)]
+    pub payment_mint_registry: Option<Account<'info, PaymentMintRegistry>>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        constraint = advertiser.is_active @ ErrorCode::AdvertiserNotActive,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Campaign::SPACE,
+        seeds = [b"campaign", advertiser.key().as_ref(), &advertiser.campaign_count.to_le_bytes()],
+        bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        mut,
+        constraint = advertiser_token_account.owner == authority.key(),
+        constraint = advertiser_token_account.mint == payment_mint.key(),
+    )]
+    pub advertiser_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = state,
+    )]
+    pub campaign_vault: Account<'info, TokenAccount>,
+
+    pub payment_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.mint == payment_mint.key(),
+        seeds = [b"fee_vault", state.mint_seed(&payment_mint.key())],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates a campaign whose budget, schedule and targeting are shared by the
+/// ads later attached to it with `create_campaign_ad`. The budget can be paid
+/// in the primary payment mint or any mint accepted by the registry.
+pub fn handler(
+    ctx: Context<CreateCampaign>,
+    encrypted_target_traits: CiphertextEnvelope,
+    duration: i64,
+    budget: u64,
+    start_at: Option<i64>,
+) -> Result<()> {
+    // Amounts are checked in the mint the budget is paid in
+    let mint = ctx.accounts.payment_mint.key();
+    let params = ctx.accounts.state.deposit_params(
+        &mint,
+        &ctx.accounts.protocol_config.params,
+        ctx.accounts.payment_mint_registry.as_deref(),
+    )?;
+    params.check_duration(duration)?;
+    require!(
+        budget >= params.min_ad_budget,
+        ErrorCode::InsufficientAdBudget
+    );
+
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+    let campaign = &mut ctx.accounts.campaign;
+    let authority = &ctx.accounts.authority;
+    let now = Clock::get()?.unix_timestamp;
+
+    // Scheduled like a standalone ad
+    let start_at = start_at.unwrap_or(now);
+    require!(
+        start_at >= now && start_at - now <= params.max_ad_duration,
+        ErrorCode::InvalidStartTime
+    );
+
+    // The same reputation cap applies as for a standalone ad
+    advertiser.refresh_reputation(now, params.reputation_half_life);
+    params.check_budget_cap(advertiser.reputation_score, budget)?;
+
+    let stored_traits = encrypted_target_traits
+        .validate(&ctx.accounts.fhe_key_registry, MAX_ENCRYPTED_TRAITS_SIZE)?;
+
+    require!(
+        ctx.accounts.advertiser_token_account.amount >= budget,
+        ErrorCode::InsufficientFunds
+    );
+
+    // The protocol fee comes out of the deposit; the rest is the shared budget
+    let fee = state.collect_fee(budget)?;
+    let net_budget = budget - fee;
+
+    if fee > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.advertiser_token_account.to_account_info(),
+            to: ctx.accounts.fee_vault.to_account_info(),
+            authority: authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, fee)?;
+    }
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.advertiser_token_account.to_account_info(),
+        to: ctx.accounts.campaign_vault.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    token::transfer(cpi_ctx, net_budget)?;
+
+    campaign.advertiser = advertiser.key();
+    campaign.encrypted_target_traits = stored_traits;
+    campaign.key_epoch = encrypted_target_traits.key_epoch;
+    campaign.trait_schema = encrypted_target_traits.trait_schema;
+    campaign.duration = duration;
+    campaign.budget = net_budget;
+    campaign.spent_budget = 0;
+    campaign.impressions = 0;
+    campaign.clicks = 0;
+    campaign.ad_count = 0;
+    campaign.open_ad_count = 0;
+    campaign.is_active = true;
+    campaign.created_at = now;
+    campaign.last_updated = now;
+    campaign.bump = ctx.bumps.campaign;
+    campaign.vault_bump = ctx.bumps.campaign_vault;
+    campaign.mint = mint;
+    campaign.start_at = start_at;
+
+    advertiser.campaign_count = advertiser
+        .campaign_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.open_campaign_count = advertiser
+        .open_campaign_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.total_budget = advertiser
+        .total_budget
+        .checked_add(net_budget)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.last_updated = now;
+
+    state.total_budget = state
+        .total_budget
+        .checked_add(net_budget)
+        .ok_or(ErrorCode::Overflow)?;
+    state.last_updated = now;
+
+    if fee > 0 {
+        emit!(ProtocolFeeCollected {
+            sequence: state.next_event_sequence()?,
+            ad: Pubkey::default(),
+            campaign: Some(campaign.key()),
+            amount: fee,
+            timestamp: now,
+        });
+    }
+    emit!(CampaignCreated {
+        sequence: state.next_event_sequence()?,
+        campaign: campaign.key(),
+        advertiser: advertiser.key(),
+        mint,
+        key_epoch: campaign.key_epoch,
+        trait_schema: campaign.trait_schema,
+        budget: net_budget,
+        duration,
+        start_at,
+        created_at: now,
+    });
+
+    msg!("Campaign created: {}", campaign.key());
+    Ok(())
+}
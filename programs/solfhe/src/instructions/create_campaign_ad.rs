@@ -0,0 +1,168 @@
+use crate::error::ErrorCode;
+use crate::events::AdCreated;
+use crate::reputation::ReputationTier;
+use crate::state::{
+    AdAccount, AdvertiserAccount, Campaign, FheKeyRegistry, Pacing, PaymentMintRegistry,
+    ProtocolConfig, StateAccount,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CreateCampaignAd<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"fhe_key_registry"], bump = fhe_key_registry.bump)]
+    pub fhe_key_registry: Account<'info, FheKeyRegistry>,
+
+    /// Required for a campaign paid in a mint other than the primary one
+    #[account(seeds = [b"payment_mint_registry"], bump = payment_mint_registry.bump)]
+    pub payment_mint_registry: Option<Account<'info, PaymentMintRegistry>>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        constraint = advertiser.is_active @ ErrorCode::AdvertiserNotActive,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        mut,
+        has_one = advertiser @ ErrorCode::Unauthorized,
+        constraint = campaign.is_active @ ErrorCode::CampaignNotActive,
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AdAccount::SPACE,
+        seeds = [b"ad", advertiser.key().as_ref(), &advertiser.ad_count.to_le_bytes()],
+        bump
+    )]
+    pub ad: Account<'info, AdAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Attaches a new creative to `campaign`. The ad takes the campaign's
+/// targeting and end time, and its settlements are charged to the campaign's
+/// shared budget. `daily_cap` and `pacing` limit what the ad itself spends
+/// per day; with `Pacing::Even` the campaign's unspent budget is paced.
+pub fn handler(
+    ctx: Context<CreateCampaignAd>,
+    content: String,
+    daily_cap: u64,
+    pacing: Pacing,
+) -> Result<()> {
+    let params = ctx.accounts.protocol_config.params;
+    params.check_content(&content)?;
+
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+    let campaign = &mut ctx.accounts.campaign;
+    let ad = &mut ctx.accounts.ad;
+    let now = Clock::get()?.unix_timestamp;
+
+    // Member ads run within the campaign's schedule
+    let start_at = campaign.start_at.max(now);
+    let ends_at = campaign.ends_at()?;
+    require!(now < ends_at, ErrorCode::CampaignExpired);
+    // Traits of an earlier epoch can no longer be scored by the coprocessor
+    require!(
+        campaign.key_epoch == ctx.accounts.fhe_key_registry.epoch,
+        ErrorCode::StaleKeyEpoch
+    );
+    // The ad's daily cap is bounded by the shared budget
+    let rates = state.settlement_rates(
+        &campaign.mint,
+        ctx.accounts.payment_mint_registry.as_deref(),
+    )?;
+    AdAccount::validate_daily_cap(daily_cap, campaign.budget, rates)?;
+
+    advertiser.refresh_reputation(now, params.reputation_half_life);
+    let pending_review =
+        params.reputation_tier(advertiser.reputation_score) == ReputationTier::Review;
+
+    ad.advertiser = advertiser.key();
+    ad.content = content.clone();
+    ad.encrypted_target_traits = campaign.encrypted_target_traits.clone();
+    ad.key_epoch = campaign.key_epoch;
+    ad.trait_schema = campaign.trait_schema;
+    ad.duration = ends_at - start_at;
+    ad.budget = 0;
+    ad.spent_budget = 0;
+    ad.impressions = 0;
+    ad.clicks = 0;
+    ad.is_active = !pending_review;
+    ad.pending_review = pending_review;
+    ad.created_at = now;
+    ad.last_updated = now;
+    ad.has_vault = true;
+    ad.vault_bump = campaign.vault_bump;
+    ad.campaign = campaign.key();
+    ad.daily_cap = daily_cap;
+    ad.pacing = pacing;
+    ad.spend_day = 0;
+    ad.day_spent = 0;
+    ad.start_at = start_at;
+    ad.refunded = 0;
+    ad.mint = campaign.mint;
+    ad.deposited = 0;
+
+    advertiser.ad_count = advertiser
+        .ad_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.open_ad_count = advertiser
+        .open_ad_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.last_updated = now;
+
+    campaign.ad_count = campaign
+        .ad_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    campaign.open_ad_count = campaign
+        .open_ad_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    campaign.last_updated = now;
+
+    state.ad_count = state.ad_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    state.last_updated = now;
+
+    emit!(AdCreated {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: advertiser.key(),
+        content,
+        key_epoch: ad.key_epoch,
+        trait_schema: ad.trait_schema,
+        budget: 0,
+        duration: ad.duration,
+        start_at,
+        mint: campaign.mint,
+        pending_review,
+        daily_cap,
+        pacing,
+        campaign: campaign.key(),
+        created_at: now,
+    });
+
+    msg!("Campaign ad created: {}", ad.key());
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use crate::error::ErrorCode;
+use crate::events::AdvertiserDeactivated;
+use crate::state::{AdvertiserAccount, AdvertiserStake, ProtocolConfig, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct DeactivateAdvertiser<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = advertiser.is_active @ ErrorCode::AdvertiserNotActive,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser_stake", advertiser.key().as_ref()],
+        bump = stake.bump,
+        has_one = advertiser,
+    )]
+    pub stake: Account<'info, AdvertiserStake>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Deregisters the advertiser and starts the stake cooldown. Its ads are left
+/// as they are, but are no longer matched, settled or resumed.
+pub fn handler(ctx: Context<DeactivateAdvertiser>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+
+    deactivate(
+        &mut ctx.accounts.state,
+        &mut ctx.accounts.advertiser,
+        &mut ctx.accounts.stake,
+        ctx.accounts.protocol_config.params.stake_cooldown,
+        now,
+    )?;
+
+    msg!("Advertiser deactivated: {}", ctx.accounts.advertiser.key());
+    Ok(())
+}
+
+/// Deactivates `advertiser` and starts the stake cooldown. Also used when a
+/// penalty takes the reputation score to zero.
+pub(crate) fn deactivate(
+    state: &mut StateAccount,
+    advertiser: &mut Account<AdvertiserAccount>,
+    stake: &mut AdvertiserStake,
+    stake_cooldown: i64,
+    now: i64,
+) -> Result<()> {
+    advertiser.is_active = false;
+    advertiser.last_updated = now;
+    stake.unlocks_at = now.checked_add(stake_cooldown).ok_or(ErrorCode::Overflow)?;
+    stake.last_updated = now;
+
+    emit!(AdvertiserDeactivated {
+        sequence: state.next_event_sequence()?,
+        advertiser: advertiser.key(),
+        authority: advertiser.authority,
+        unlocks_at: stake.unlocks_at,
+        timestamp: now,
+    });
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use crate::error::ErrorCode;
+use crate::events::{AdExpired, BudgetRefunded};
+use crate::state::{AdAccount, AdvertiserAccount, StateAccount};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+/// Permissionless crank: anyone may expire an ad once it has ended. The
+/// refund can only be paid into a token account of the advertiser.
+#[derive(Accounts)]
+pub struct ExpireAd<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(mut, address = ad.advertiser @ ErrorCode::Unauthorized)]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(mut, constraint = ad.has_vault @ ErrorCode::AdVaultNotMigrated)]
+    pub ad: Account<'info, AdAccount>,
+
+    /// The ad's own vault, or its campaign's vault for a campaign ad, which
+    /// is left untouched
+    #[account(mut, address = ad.budget_vault(&ad.key())?)]
+    pub ad_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = advertiser_token_account.owner == advertiser.authority,
+        constraint = advertiser_token_account.mint == ad.mint,
+    )]
+    pub advertiser_token_account: Account<'info, TokenAccount>,
+
+    pub caller: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Marks an ad that has run its course inactive and refunds its unspent
+/// budget. The ad stays open, so its stats remain readable until the
+/// advertiser calls `close_ad`. A campaign ad has no budget of its own; the
+/// campaign's is refunded by `close_campaign`.
+pub fn handler(ctx: Context<ExpireAd>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let ad = &ctx.accounts.ad;
+    require!(now >= ad.ends_at()?, ErrorCode::AdNotExpired);
+
+    let refund = ad.remaining_budget()?;
+    require!(ad.is_active || refund > 0, ErrorCode::AdAlreadyExpired);
+
+    if refund > 0 {
+        // The vault is owned by the state PDA
+        let state_bump = [ctx.accounts.state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"state", &state_bump]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.ad_vault.to_account_info(),
+            to: ctx.accounts.advertiser_token_account.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token::transfer(cpi_ctx, refund)?;
+    }
+
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+    let ad = &mut ctx.accounts.ad;
+
+    ad.is_active = false;
+    ad.refunded = ad.refunded.checked_add(refund).ok_or(ErrorCode::Overflow)?;
+    ad.last_updated = now;
+
+    // Budget totals count deposits net of refunds
+    advertiser.total_budget = advertiser
+        .total_budget
+        .checked_sub(refund)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.last_updated = now;
+    state.total_budget = state
+        .total_budget
+        .checked_sub(refund)
+        .ok_or(ErrorCode::Overflow)?;
+    state.last_updated = now;
+
+    if refund > 0 {
+        emit!(BudgetRefunded {
+            sequence: state.next_event_sequence()?,
+            ad: ad.key(),
+            advertiser: advertiser.key(),
+            recipient: ctx.accounts.advertiser_token_account.key(),
+            amount: refund,
+            timestamp: now,
+        });
+    }
+    emit!(AdExpired {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: advertiser.key(),
+        spent_budget: ad.spent_budget,
+        refund,
+        timestamp: now,
+    });
+
+    msg!("Ad expired, refunded {} tokens", refund);
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use crate::error::ErrorCode;
+use crate::events::AdDurationExtended;
+use crate::state::{AdAccount, AdvertiserAccount, ProtocolConfig, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ExtendAdDuration<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = advertiser.is_active @ ErrorCode::AdvertiserNotActive,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(mut, has_one = advertiser @ ErrorCode::Unauthorized)]
+    pub ad: Account<'info, AdAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Extends a running or scheduled ad. Ads that have ended stay ended, even
+/// before `expire_ad` refunds them.
+pub fn handler(ctx: Context<ExtendAdDuration>, additional_duration: i64) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let ad = &mut ctx.accounts.ad;
+    let params = &ctx.accounts.protocol_config.params;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(ad.refunded == 0, ErrorCode::AdAlreadyExpired);
+    require!(now < ad.ends_at()?, ErrorCode::AdExpired);
+
+    // The total run time, measured from the start, stays within the same bound as create_ad
+    let duration = ad
+        .duration
+        .checked_add(additional_duration)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(additional_duration > 0, ErrorCode::InvalidAdDuration);
+    params.check_duration(duration)?;
+
+    ad.duration = duration;
+    ad.last_updated = now;
+
+    emit!(AdDurationExtended {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: ad.advertiser,
+        duration,
+        ends_at: ad.ends_at()?,
+        timestamp: ad.last_updated,
+    });
+
+    msg!("Ad duration extended to {} seconds", duration);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::ProtocolParams;
+    use crate::test_utils::{install_syscalls, read_account, MockAccount, TEST_TIMESTAMP};
+    use anchor_lang::solana_program::pubkey::Pubkey;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    struct Fixture {
+        state: MockAccount,
+        protocol_config: MockAccount,
+        advertiser: MockAccount,
+        ad: MockAccount,
+        authority: MockAccount,
+    }
+
+    /// An ad that started a day ago and runs for `duration`.
+    fn create_mock_accounts(advertiser_is_active: bool, duration: i64, refunded: u64) -> Fixture {
+        let program_id = crate::ID;
+        let authority_pubkey = Pubkey::new_unique();
+        let (state_pubkey, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
+        let (protocol_config_pubkey, protocol_config_bump) =
+            Pubkey::find_program_address(&[b"protocol_config"], &program_id);
+        let (advertiser_pubkey, advertiser_bump) =
+            Pubkey::find_program_address(&[b"advertiser", authority_pubkey.as_ref()], &program_id);
+
+        // The seeds constraints derive each address from its stored bump
+        let state_account = StateAccount {
+            bump: state_bump,
+            ..StateAccount::default()
+        };
+        let protocol_config = ProtocolConfig {
+            params: ProtocolParams::default(),
+            bump: protocol_config_bump,
+            ..ProtocolConfig::default()
+        };
+        let advertiser = AdvertiserAccount {
+            authority: authority_pubkey,
+            is_active: advertiser_is_active,
+            bump: advertiser_bump,
+            ..AdvertiserAccount::default()
+        };
+        let ad = AdAccount {
+            advertiser: advertiser_pubkey,
+            budget: 100_000_000,
+            duration,
+            start_at: TEST_TIMESTAMP - DAY,
+            created_at: TEST_TIMESTAMP - DAY,
+            refunded,
+            is_active: refunded == 0,
+            ..AdAccount::default()
+        };
+
+        Fixture {
+            state: MockAccount::program_account(state_pubkey, &state_account, StateAccount::SPACE),
+            protocol_config: MockAccount::program_account(
+                protocol_config_pubkey,
+                &protocol_config,
+                ProtocolConfig::SPACE,
+            ),
+            advertiser: MockAccount::program_account(
+                advertiser_pubkey,
+                &advertiser,
+                AdvertiserAccount::SPACE,
+            ),
+            ad: MockAccount::program_account(Pubkey::new_unique(), &ad, AdAccount::SPACE),
+            authority: MockAccount::signer(authority_pubkey),
+        }
+    }
+
+    /// Runs the account constraints, then the handler.
+    fn extend(fixture: &mut Fixture, additional_duration: i64) -> Result<()> {
+        install_syscalls();
+        let program_id = crate::ID;
+        let infos = [
+            fixture.state.info(),
+            fixture.protocol_config.info(),
+            fixture.advertiser.info(),
+            fixture.ad.info(),
+            fixture.authority.info(),
+        ];
+
+        let mut bumps = ExtendAdDurationBumps::default();
+        let mut accounts = ExtendAdDuration::try_accounts(
+            &program_id,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut Default::default(),
+        )?;
+        let context = Context::new(&program_id, &mut accounts, &[], bumps);
+
+        handler(context, additional_duration)?;
+        accounts.exit(&program_id)
+    }
+
+    #[test]
+    fn test_extend_ad_duration() {
+        let mut fixture = create_mock_accounts(true, 2 * DAY, 0);
+
+        extend(&mut fixture, DAY).unwrap();
+
+        let ad: AdAccount = read_account(&fixture.ad.info());
+        assert_eq!(ad.duration, 3 * DAY);
+        assert_eq!(ad.ends_at().unwrap(), TEST_TIMESTAMP + 2 * DAY);
+        assert_eq!(ad.last_updated, TEST_TIMESTAMP);
+    }
+
+    #[test]
+    fn test_extend_ad_duration_rejects_ended_ads() {
+        let mut fixture = create_mock_accounts(true, DAY, 0);
+
+        assert_eq!(
+            extend(&mut fixture, DAY).unwrap_err(),
+            ErrorCode::AdExpired.into()
+        );
+    }
+
+    #[test]
+    fn test_extend_ad_duration_rejects_refunded_ads() {
+        let mut fixture = create_mock_accounts(true, 2 * DAY, 1);
+
+        assert_eq!(
+            extend(&mut fixture, DAY).unwrap_err(),
+            ErrorCode::AdAlreadyExpired.into()
+        );
+    }
+
+    #[test]
+    fn test_extend_ad_duration_rejects_inactive_advertisers() {
+        let mut fixture = create_mock_accounts(false, 2 * DAY, 0);
+
+        assert_eq!(
+            extend(&mut fixture, DAY).unwrap_err(),
+            ErrorCode::AdvertiserNotActive.into()
+        );
+    }
+}
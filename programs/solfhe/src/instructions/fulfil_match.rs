@@ -0,0 +1,107 @@
+use crate::error::ErrorCode;
+use crate::events::AdsMatched;
+use crate::state::{
+    EncryptedMatch, FheKeyRegistry, MatchRequest, MatchedAdsAccount, StateAccount,
+    MAX_CIPHERTEXT_URI_LENGTH,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct FulfilMatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = coprocessor @ ErrorCode::Unauthorized,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"fhe_key_registry"], bump = fhe_key_registry.bump)]
+    pub fhe_key_registry: Account<'info, FheKeyRegistry>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"match_request", user.key().as_ref()],
+        bump = match_request.bump,
+        has_one = user,
+        constraint = match_request.key_epoch == fhe_key_registry.epoch @ ErrorCode::StaleKeyEpoch,
+    )]
+    pub match_request: Account<'info, MatchRequest>,
+
+    #[account(
+        init_if_needed,
+        payer = coprocessor,
+        space = 8 + MatchedAdsAccount::SPACE,
+        seeds = [b"matched_ads", user.key().as_ref()],
+        bump
+    )]
+    pub matched_ads: Account<'info, MatchedAdsAccount>,
+
+    /// Receives the request's rent back once it is fulfilled
+    #[account(mut)]
+    pub user: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub coprocessor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Records the coprocessor's scores. The score ciphertexts are too large for
+/// a transaction, so they are published at `scores_uri` and only their hashes
+/// are stored.
+pub fn handler(
+    ctx: Context<FulfilMatch>,
+    scores_uri: String,
+    score_hashes: Vec<[u8; 32]>,
+) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let match_request = &ctx.accounts.match_request;
+    let matched_ads = &mut ctx.accounts.matched_ads;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(
+        !match_request.is_expired(now),
+        ErrorCode::MatchRequestExpired
+    );
+
+    // One score hash per candidate, in request order
+    require!(
+        score_hashes.len() == match_request.candidate_ads.len(),
+        ErrorCode::ScoreCountMismatch
+    );
+    require!(
+        !scores_uri.is_empty() && scores_uri.len() <= MAX_CIPHERTEXT_URI_LENGTH,
+        ErrorCode::InvalidCiphertextUri
+    );
+
+    matched_ads.user = match_request.user;
+    matched_ads.scores_uri = scores_uri;
+    matched_ads.matches = match_request
+        .candidate_ads
+        .iter()
+        .zip(score_hashes)
+        .map(|(ad, score_hash)| EncryptedMatch {
+            ad: *ad,
+            score_hash,
+            key_epoch: match_request.key_epoch,
+            matched_at: now,
+        })
+        .collect();
+    matched_ads.last_updated = now;
+    matched_ads.bump = ctx.bumps.matched_ads;
+
+    emit!(AdsMatched {
+        sequence: state.next_event_sequence()?,
+        user: matched_ads.user,
+        match_request: match_request.key(),
+        matched_ads: matched_ads.key(),
+        match_count: matched_ads.matches.len() as u8,
+        timestamp: now,
+    });
+
+    msg!("Match request fulfilled for user: {}", matched_ads.user);
+    Ok(())
+}
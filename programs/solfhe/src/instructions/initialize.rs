@@ -1,4 +1,3 @@
-use crate::error::ErrorCode;
 use crate::events::ProgramInitialized;
 use crate::state::*;
 use anchor_lang::prelude::*;
@@ -14,29 +13,69 @@ pub struct Initialize<'info> {
     )]
     pub state: Account<'info, StateAccount>,
 
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + FheKeyRegistry::SPACE,
+        seeds = [b"fhe_key_registry"],
+        bump
+    )]
+    pub fhe_key_registry: Account<'info, FheKeyRegistry>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProtocolConfig::SPACE,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<Initialize>) -> Result<()> {
+pub fn handler(
+    ctx: Context<Initialize>,
+    coprocessor: Pubkey,
+    fhe_keys: FheKeyParams,
+) -> Result<()> {
     let state = &mut ctx.accounts.state;
+    let fhe_key_registry = &mut ctx.accounts.fhe_key_registry;
+    let protocol_config = &mut ctx.accounts.protocol_config;
 
     // Initialize the state account
     state.authority = ctx.accounts.authority.key();
+    state.pending_authority = Pubkey::default();
+    state.coprocessor = coprocessor;
     state.advertiser_count = 0;
     state.user_count = 0;
     state.ad_count = 0;
     state.total_budget = 0;
+    state.is_paused = false;
     state.last_updated = Clock::get()?.unix_timestamp;
+    state.event_sequence = 0;
 
     // Set the bump to be used in future PDA derivations
-    state.bump = *ctx.bumps.get("state").ok_or(ErrorCode::BumpNotFound)?;
+    state.bump = ctx.bumps.state;
+
+    // Register the network FHE keys as epoch 0
+    fhe_key_registry.set_keys(fhe_keys, state.last_updated, false)?;
+    fhe_key_registry.bump = ctx.bumps.fhe_key_registry;
+
+    // Start from the default parameters; the authority can tune them later
+    protocol_config.params = ProtocolParams::default();
+    protocol_config.last_updated = state.last_updated;
+    protocol_config.bump = ctx.bumps.protocol_config;
 
     // Emit an event for program initialization
     emit!(ProgramInitialized {
+        sequence: state.next_event_sequence()?,
         authority: state.authority,
+        coprocessor: state.coprocessor,
+        fhe_key_epoch: fhe_key_registry.epoch,
         timestamp: state.last_updated,
     });
 
@@ -47,35 +86,67 @@ pub fn handler(ctx: Context<Initialize>) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{install_syscalls, read_account, TEST_TIMESTAMP};
     use anchor_lang::solana_program::pubkey::Pubkey;
+    use anchor_lang::system_program;
 
     #[test]
     fn test_initialize() {
-        let program_id = Pubkey::new_unique();
+        install_syscalls();
+        let program_id = crate::ID;
         let authority_pubkey = Pubkey::new_unique();
+        let coprocessor_pubkey = Pubkey::new_unique();
         let (state_pubkey, _) = Pubkey::find_program_address(&[b"state"], &program_id);
+        let (registry_pubkey, _) =
+            Pubkey::find_program_address(&[b"fhe_key_registry"], &program_id);
+        let (config_pubkey, _) = Pubkey::find_program_address(&[b"protocol_config"], &program_id);
+
+        let mut lamports = 0;
+        let mut data = vec![0; 8 + FheKeyRegistry::SPACE];
+        let registry_account_info = AccountInfo::new(
+            &registry_pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &program_id,
+            false,
+            0,
+        );
+
+        let mut config_lamports = 0;
+        let mut config_data = vec![0; 8 + ProtocolConfig::SPACE];
+        let config_account_info = AccountInfo::new(
+            &config_pubkey,
+            false,
+            true,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0,
+        );
 
         let mut lamports = 0;
-        let mut data = vec![0; StateAccount::SPACE];
-        let owner = program_id;
+        let mut data = vec![0; 8 + StateAccount::SPACE];
         let state_account_info = AccountInfo::new(
             &state_pubkey,
             false,
             true,
             &mut lamports,
             &mut data,
-            &owner,
+            &program_id,
             false,
             0,
         );
 
         let mut lamports = 1000000000; // 1 SOL
         let mut data = vec![];
-        let owner = Pubkey::default();
+        let owner = system_program::ID;
         let authority_account_info = AccountInfo::new(
             &authority_pubkey,
             true,
-            false,
+            true,
             &mut lamports,
             &mut data,
             &owner,
@@ -83,49 +154,71 @@ mod tests {
             0,
         );
 
-        let system_program_id = Pubkey::new_unique();
+        let system_program_id = system_program::ID;
         let mut lamports = 0;
         let mut data = vec![];
+        let loader = Pubkey::default();
         let system_program_account_info = AccountInfo::new(
             &system_program_id,
             false,
             false,
             &mut lamports,
             &mut data,
-            &owner,
-            false,
+            &loader,
+            true,
             0,
         );
 
-        let accounts = Initialize {
-            state: Account::try_from(&state_account_info).unwrap(),
+        let mut accounts = Initialize {
+            state: Account::try_from_unchecked(&state_account_info).unwrap(),
+            fhe_key_registry: Account::try_from_unchecked(&registry_account_info).unwrap(),
+            protocol_config: Account::try_from_unchecked(&config_account_info).unwrap(),
             authority: Signer::try_from(&authority_account_info).unwrap(),
             system_program: Program::try_from(&system_program_account_info).unwrap(),
         };
 
-        let mut context = Context::new(
-            program_id,
-            accounts,
-            &[
-                &state_account_info,
-                &authority_account_info,
-                &system_program_account_info,
-            ],
-            BTreeMap::new(),
-            BTreeMap::new(),
+        let context = Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            InitializeBumps {
+                state: 255,
+                fhe_key_registry: 254,
+                protocol_config: 253,
+            },
         );
 
-        context.bumps.insert("state".to_string(), 255);
+        let fhe_keys = FheKeyParams {
+            public_key_hash: [1; 32],
+            server_key_hash: [2; 32],
+            key_uri: "https://keys.solfhe.xyz/epoch-0".to_string(),
+            parameter_set: 1,
+        };
 
-        handler(context).unwrap();
+        handler(context, coprocessor_pubkey, fhe_keys.clone()).unwrap();
+        accounts.exit(&program_id).unwrap();
 
-        let state = StateAccount::try_from_slice(&state_account_info.data.borrow()).unwrap();
+        let state: StateAccount = read_account(&state_account_info);
         assert_eq!(state.authority, authority_pubkey);
+        assert_eq!(state.coprocessor, coprocessor_pubkey);
         assert_eq!(state.advertiser_count, 0);
         assert_eq!(state.user_count, 0);
         assert_eq!(state.ad_count, 0);
         assert_eq!(state.total_budget, 0);
+        assert!(!state.is_paused);
         assert_eq!(state.bump, 255);
-        assert!(state.last_updated > 0);
+        assert_eq!(state.last_updated, TEST_TIMESTAMP);
+        assert_eq!(state.event_sequence, 1);
+
+        let registry: FheKeyRegistry = read_account(&registry_account_info);
+        assert_eq!(registry.public_key_hash, fhe_keys.public_key_hash);
+        assert_eq!(registry.server_key_hash, fhe_keys.server_key_hash);
+        assert_eq!(registry.key_uri, fhe_keys.key_uri);
+        assert_eq!(registry.epoch, 0);
+        assert_eq!(registry.bump, 254);
+
+        let config: ProtocolConfig = read_account(&config_account_info);
+        assert_eq!(config.params, ProtocolParams::default());
+        assert_eq!(config.bump, 253);
     }
 }
@@ -0,0 +1,50 @@
+use crate::error::ErrorCode;
+use crate::events::FeeVaultInitialized;
+use crate::state::StateAccount;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"fee_vault"],
+        bump,
+        token::mint = payment_mint,
+        token::authority = state,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    #[account(address = state.payment_mint)]
+    pub payment_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<InitializeFeeVault>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let fee_vault = &ctx.accounts.fee_vault;
+
+    emit!(FeeVaultInitialized {
+        sequence: state.next_event_sequence()?,
+        fee_vault: fee_vault.key(),
+        mint: fee_vault.mint,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Fee vault initialized: {}", fee_vault.key());
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use crate::error::ErrorCode;
+use crate::events::RewardsVaultInitialized;
+use crate::state::StateAccount;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct InitializeRewardsVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"rewards_vault"],
+        bump,
+        token::mint = payment_mint,
+        token::authority = state,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+
+    #[account(address = state.payment_mint)]
+    pub payment_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the vault that holds user rewards in the primary payment mint.
+/// The unkeyed `[b"treasury"]` account is the escrow of legacy ads, drained by
+/// `migrate_ad_vault`, and never holds rewards.
+pub fn handler(ctx: Context<InitializeRewardsVault>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let rewards_vault = &ctx.accounts.rewards_vault;
+
+    emit!(RewardsVaultInitialized {
+        sequence: state.next_event_sequence()?,
+        rewards_vault: rewards_vault.key(),
+        mint: rewards_vault.mint,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Rewards vault initialized: {}", rewards_vault.key());
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use crate::error::ErrorCode;
+use crate::events::AdVaultMigrated;
+use crate::state::{AdAccount, AdvertiserAccount, StateAccount};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+/// Moves the unspent budget of an ad funded into the legacy shared treasury
+/// into the ad's own vault.
+#[derive(Accounts)]
+pub struct MigrateAdVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        mut,
+        has_one = advertiser @ ErrorCode::Unauthorized,
+        constraint = !ad.has_vault @ ErrorCode::AdVaultAlreadyMigrated,
+    )]
+    pub ad: Account<'info, AdAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"ad_vault", ad.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = state,
+    )]
+    pub ad_vault: Account<'info, TokenAccount>,
+
+    #[account(address = state.payment_mint)]
+    pub payment_mint: Account<'info, Mint>,
+
+    #[account(mut, constraint = treasury.mint == state.payment_mint, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateAdVault>) -> Result<()> {
+    let amount = ctx.accounts.ad.remaining_budget()?;
+
+    if amount > 0 {
+        let state_bump = [ctx.accounts.state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"state", &state_bump]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.treasury.to_account_info(),
+            to: ctx.accounts.ad_vault.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token::transfer(cpi_ctx, amount)?;
+    }
+
+    let state = &mut ctx.accounts.state;
+    let ad = &mut ctx.accounts.ad;
+
+    ad.has_vault = true;
+    ad.mint = state.payment_mint;
+    ad.vault_bump = ctx.bumps.ad_vault;
+    ad.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(AdVaultMigrated {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: ad.advertiser,
+        ad_vault: ctx.accounts.ad_vault.key(),
+        amount,
+        timestamp: ad.last_updated,
+    });
+
+    msg!("Moved {} tokens from the treasury to the ad vault", amount);
+    Ok(())
+}
@@ -1,143 +1,122 @@
 //! # solFHE: Fully Homomorphic Encryption-based Advertising Protocol
 //! # Author: @virjilakrum 🦀
-//! This module serves as the root of the solFHE crate, organizing and re-exporting
-//! all public components of the protocol. solFHE enables personalized advertising
-//! while maintaining user privacy through the use of Fully Homomorphic Encryption (FHE).
+//! Instruction handlers of the solFHE program, one module per instruction.
+//! Each module defines the instruction's `Accounts` struct and a `handler`
+//! that the `#[program]` module in `lib.rs` dispatches to. Account layouts
+//! live in `state`, ciphertext envelopes in `envelope`, advertiser scoring
+//! in `reputation` and input checks in `validation`.
 //!
-//! ## Module Structure
+//! ## Instructions
 //!
-//! - `instructions`: Contains all instruction handlers for the Solana program.
-//! - `state`: Defines the account structures used in the protocol.
-//! - `error`: Custom error types for the solFHE program.
-//! - `events`: Event definitions for important protocol actions.
-//! - `fhe`: Utility functions and traits for FHE operations.
-//! - `validation`: Input validation and security check functions.
-//! - `constants`: Protocol-wide constant values.
-//!
-//! ## Main Features
-//!
-//! - Create and manage advertiser accounts
-//! - Submit and process encrypted user profiles
-//! - Create and manage ad campaigns with FHE-encrypted targeting data
-//! - Match ads to users using FHE computations
-//! - Handle token transfers and budget management
-
-// Re-export essential Anchor and Solana types
-pub use anchor_lang::prelude::*;
-pub use anchor_spl::token::{self, Token, TokenAccount, Transfer};
-
-// Define and re-export submodules
-pub mod constants;
-pub mod error;
-pub mod events;
-pub mod fhe;
-pub mod instructions;
-pub mod state;
-pub mod validation;
-
-// Re-export main instruction handlers for easier access
-pub use instructions::{
-    create_ad::*, initialize::*, match_ads::*, register_advertiser::*, submit_user_profile::*,
-    update_ad_status::*,
-};
-
-// Re-export state structures
-pub use state::{AdAccount, AdvertiserAccount, MatchedAdsAccount, StateAccount, UserProfile};
-
-// Re-export error types
-pub use error::ErrorCode;
-
-// Re-export event structures
-pub use events::{AdCreated, AdsMatched, AdvertiserRegistered, UserProfileSubmitted};
-
-// Re-export important constants
-pub use constants::{
-    FHE_TRAITS_COUNT, MAX_AD_DURATION, MAX_CONTENT_LENGTH, MIN_AD_BUDGET, MIN_AD_DURATION,
-};
-
-// Define the entrypoint for the Solana program
-entrypoint!(process_instruction);
-
-pub fn process_instruction(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    let instruction = anchor_lang::AnchorDeserialize::deserialize(&mut &instruction_data[..])?;
-    let mut acc_iter = &mut accounts.iter();
-
-    match instruction {
-        solFHEInstruction::Initialize {} => {
-            instructions::initialize::handler(Context::new(program_id, acc_iter, instruction_data)?)
-        }
-        solFHEInstruction::RegisterAdvertiser { name, email } => {
-            instructions::register_advertiser::handler(
-                Context::new(program_id, acc_iter, instruction_data)?,
-                name,
-                email,
-            )
-        }
-        solFHEInstruction::CreateAd {
-            content,
-            encrypted_target_traits,
-            duration,
-            budget,
-        } => instructions::create_ad::handler(
-            Context::new(program_id, acc_iter, instruction_data)?,
-            content,
-            encrypted_target_traits,
-            duration,
-            budget,
-        ),
-        solFHEInstruction::SubmitUserProfile {
-            encrypted_profile_data,
-        } => instructions::submit_user_profile::handler(
-            Context::new(program_id, acc_iter, instruction_data)?,
-            encrypted_profile_data,
-        ),
-        solFHEInstruction::MatchAds {
-            encrypted_user_traits,
-        } => instructions::match_ads::handler(
-            Context::new(program_id, acc_iter, instruction_data)?,
-            encrypted_user_traits,
-        ),
-        solFHEInstruction::UpdateAdStatus { is_active } => instructions::update_ad_status::handler(
-            Context::new(program_id, acc_iter, instruction_data)?,
-            is_active,
-        ),
-    }
-}
-
-/// Defines the instructions supported by the solFHE program
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub enum solFHEInstruction {
-    Initialize {},
-    RegisterAdvertiser {
-        name: String,
-        email: String,
-    },
-    CreateAd {
-        content: String,
-        encrypted_target_traits: Vec<u8>,
-        duration: i64,
-        budget: u64,
-    },
-    SubmitUserProfile {
-        encrypted_profile_data: Vec<u8>,
-    },
-    MatchAds {
-        encrypted_user_traits: Vec<u8>,
-    },
-    UpdateAdStatus {
-        is_active: bool,
-    },
-}
+//! - Governance: `initialize`, authority handover, protocol pause,
+//!   `update_protocol_config` and its setters, payment mints, settlement
+//!   rates, the protocol fee and FHE key rotation.
+//! - Advertisers: registration with a locked stake, profile updates, email
+//!   verification, deactivation, reputation reports and slashing.
+//! - Ads and campaigns: creation with FHE-encrypted targeting, pausing,
+//!   review, top-ups, expiry and closure, each budget escrowed in a vault.
+//! - Settlement: `record_impression` and `record_click` pay publishers and
+//!   users through the shared `Settle` accounts of `settlement`, which is
+//!   why only `settlement` is re-exported; rewards are claimed with
+//!   `claim_rewards`.
+//! - Matching: `submit_user_profile`, then `request_match`, which the
+//!   off-chain FHE coprocessor answers with `fulfil_match`, or
+//!   `cancel_match`.
+//! - Maintenance: `migrate_ad_vault` moves ads funded by earlier program
+//!   versions into their own vaults.
 
-#[cfg(test)]
-mod tests {
+pub mod accept_authority;
+pub mod add_payment_mint;
+pub mod approve_ad;
+pub mod cancel_match;
+pub mod claim_rewards;
+pub mod close_ad;
+pub mod close_advertiser;
+pub mod close_campaign;
+pub mod create_ad;
+pub mod create_campaign;
+pub mod create_campaign_ad;
+pub mod deactivate_advertiser;
+pub mod expire_ad;
+pub mod extend_ad_duration;
+pub mod fulfil_match;
+pub mod initialize;
+pub mod initialize_fee_vault;
+pub mod initialize_rewards_vault;
+pub mod migrate_ad_vault;
+pub mod pause_ad;
+pub mod pause_protocol;
+pub mod record_click;
+pub mod record_impression;
+pub mod register_advertiser;
+pub mod register_publisher;
+pub mod report_advertiser;
+pub mod request_match;
+pub mod resume_ad;
+pub mod resume_protocol;
+pub mod revoke_publisher;
+pub mod rotate_fhe_keys;
+pub mod set_authority;
+pub mod set_limits;
+pub mod set_match_threshold;
+pub mod set_payment_mint;
+pub mod set_protocol_fee;
+pub mod set_settlement_rates;
+pub mod settlement;
+pub mod slash_advertiser;
+pub mod submit_user_profile;
+pub mod top_up_ad;
+pub mod update_ad_content;
+pub mod update_advertiser;
+pub mod update_payment_mint;
+pub mod update_protocol_config;
+pub mod verify_advertiser_email;
+pub mod withdraw_fees;
+pub mod withdraw_stake;
 
-    /*  Import test modules here
-    Example: mod test_initialize;
-    mod test_create_ad;
-    This allows for organizing tests into separate files for better maintainability */
-}
+pub use accept_authority::*;
+pub use add_payment_mint::*;
+pub use approve_ad::*;
+pub use cancel_match::*;
+pub use claim_rewards::*;
+pub use close_ad::*;
+pub use close_advertiser::*;
+pub use close_campaign::*;
+pub use create_ad::*;
+pub use create_campaign::*;
+pub use create_campaign_ad::*;
+pub use deactivate_advertiser::*;
+pub use expire_ad::*;
+pub use extend_ad_duration::*;
+pub use fulfil_match::*;
+pub use initialize::*;
+pub use initialize_fee_vault::*;
+pub use initialize_rewards_vault::*;
+pub use migrate_ad_vault::*;
+pub use pause_ad::*;
+pub use pause_protocol::*;
+pub use register_advertiser::*;
+pub use register_publisher::*;
+pub use report_advertiser::*;
+pub use request_match::*;
+pub use resume_ad::*;
+pub use resume_protocol::*;
+pub use revoke_publisher::*;
+pub use rotate_fhe_keys::*;
+pub use set_authority::*;
+pub use set_limits::*;
+pub use set_match_threshold::*;
+pub use set_payment_mint::*;
+pub use set_protocol_fee::*;
+pub use set_settlement_rates::*;
+pub use settlement::*;
+pub use slash_advertiser::*;
+pub use submit_user_profile::*;
+pub use top_up_ad::*;
+pub use update_ad_content::*;
+pub use update_advertiser::*;
+pub use update_payment_mint::*;
+pub use update_protocol_config::*;
+pub use verify_advertiser_email::*;
+pub use withdraw_fees::*;
+pub use withdraw_stake::*;
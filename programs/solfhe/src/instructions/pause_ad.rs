@@ -0,0 +1,47 @@
+use crate::error::ErrorCode;
+use crate::events::AdPaused;
+use crate::state::{AdAccount, AdvertiserAccount, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct PauseAd<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(mut, has_one = advertiser @ ErrorCode::Unauthorized)]
+    pub ad: Account<'info, AdAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<PauseAd>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let ad = &mut ctx.accounts.ad;
+
+    require!(ad.is_active, ErrorCode::AdNotActive);
+
+    ad.is_active = false;
+    ad.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(AdPaused {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: ad.advertiser,
+        timestamp: ad.last_updated,
+    });
+
+    msg!("Ad paused: {}", ad.key());
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::error::ErrorCode;
+use crate::events::ProtocolPaused;
+use crate::state::StateAccount;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct PauseProtocol<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<PauseProtocol>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+
+    require!(!state.is_paused, ErrorCode::ProtocolPaused);
+
+    state.is_paused = true;
+    state.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(ProtocolPaused {
+        sequence: state.next_event_sequence()?,
+        timestamp: state.last_updated,
+    });
+
+    msg!("Protocol paused");
+    Ok(())
+}
@@ -0,0 +1,6 @@
+use super::settlement::{settle, Settle, Settlement};
+use anchor_lang::prelude::*;
+
+pub fn handler(ctx: Context<Settle>, count: u32) -> Result<()> {
+    settle(ctx, Settlement::Click, count)
+}
@@ -1,23 +1,24 @@
-use anchor_lang::prelude::*;
-use crate::state::{StateAccount, AdvertiserAccount};
 use crate::error::ErrorCode;
 use crate::events::AdvertiserRegistered;
-
-// Constants
-const MAX_NAME_LENGTH: usize = 50;
-const MAX_EMAIL_LENGTH: usize = 100;
-const MIN_DEPOSIT_AMOUNT: u64 = 10_000_000; // 0.01 SOL 🤑
+use crate::reputation::INITIAL_REPUTATION;
+use crate::state::{AdvertiserAccount, AdvertiserStake, ProtocolConfig, StateAccount};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
 
 #[derive(Accounts)]
-#[instruction(name: String, email: String)]
+#[instruction(name: String, email_commitment: [u8; 32])]
 pub struct RegisterAdvertiser<'info> {
     #[account(
         mut,
         seeds = [b"state"],
         bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
     )]
     pub state: Account<'info, StateAccount>,
 
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
     #[account(
         init,
         payer = authority,
@@ -27,60 +28,91 @@ pub struct RegisterAdvertiser<'info> {
     )]
     pub advertiser: Account<'info, AdvertiserAccount>,
 
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AdvertiserStake::SPACE,
+        seeds = [b"advertiser_stake", advertiser.key().as_ref()],
+        bump
+    )]
+    pub stake: Account<'info, AdvertiserStake>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<RegisterAdvertiser>, name: String, email: String) -> Result<()> {
+/// Registers the signer as an advertiser. `email_commitment` is computed
+/// off-chain with `AdvertiserAccount::email_commitment`; the email itself is
+/// never submitted.
+pub fn handler(
+    ctx: Context<RegisterAdvertiser>,
+    name: String,
+    email_commitment: [u8; 32],
+) -> Result<()> {
     // Validate input data
-    require!(
-        !name.is_empty() && name.len() <= MAX_NAME_LENGTH,
-        ErrorCode::InvalidAdvertiserName
-    );
-    require!(
-        !email.is_empty() && email.len() <= MAX_EMAIL_LENGTH,
-        ErrorCode::InvalidAdvertiserEmail
-    );
+    AdvertiserAccount::validate_profile(&name, &email_commitment)?;
 
     let state = &mut ctx.accounts.state;
     let advertiser = &mut ctx.accounts.advertiser;
     let authority = &ctx.accounts.authority;
 
-    // Check if the advertiser is already registered
-    require!(
-        !state.is_advertiser_registered(&authority.key()),
-        ErrorCode::AdvertiserAlreadyRegistered
-    );
+    // Re-registration is rejected by `init`: the advertiser PDA already exists
 
-    // Ensure the advertiser has enough balance for the minimum deposit
+    // Lock the stake; it is returned by withdraw_stake after deactivation
+    let stake_amount = ctx.accounts.protocol_config.params.advertiser_stake;
     require!(
-        authority.lamports() >= MIN_DEPOSIT_AMOUNT,
+        authority.lamports() >= stake_amount,
         ErrorCode::InsufficientFunds
     );
+    let cpi_accounts = Transfer {
+        from: authority.to_account_info(),
+        to: ctx.accounts.stake.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.system_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    system_program::transfer(cpi_ctx, stake_amount)?;
 
     // Initialize the advertiser account
     advertiser.authority = authority.key();
     advertiser.name = name.clone();
-    advertiser.email = email.clone();
+    advertiser.email_commitment = email_commitment;
+    advertiser.email_verified_at = 0;
     advertiser.ad_count = 0;
+    advertiser.open_ad_count = 0;
     advertiser.total_budget = 0;
-    advertiser.reputation_score = 100; // Initial reputation score
+    advertiser.reputation_score = INITIAL_REPUTATION;
     advertiser.is_active = true;
     advertiser.created_at = Clock::get()?.unix_timestamp;
     advertiser.last_updated = advertiser.created_at;
+    advertiser.reputation_updated_at = advertiser.created_at;
+    advertiser.bump = ctx.bumps.advertiser;
+
+    let stake = &mut ctx.accounts.stake;
+    stake.advertiser = advertiser.key();
+    stake.amount = stake_amount;
+    stake.total_slashed = 0;
+    stake.unlocks_at = 0;
+    stake.created_at = advertiser.created_at;
+    stake.last_updated = advertiser.created_at;
+    stake.bump = ctx.bumps.stake;
 
     // Update the state account
-    state.advertiser_count = state.advertiser_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    state.advertiser_count = state
+        .advertiser_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
     state.last_updated = Clock::get()?.unix_timestamp;
 
     // Emit an event for advertiser registration
     emit!(AdvertiserRegistered {
+        sequence: state.next_event_sequence()?,
         advertiser: advertiser.key(),
         authority: authority.key(),
         name: name.clone(),
-        email: email.clone(),
+        email_commitment,
+        stake: stake_amount,
         timestamp: advertiser.created_at,
     });
 
@@ -88,137 +120,168 @@ pub fn handler(ctx: Context<RegisterAdvertiser>, name: String, email: String) ->
     Ok(())
 }
 
-// Helper function to validate email format
-fn is_valid_email(email: &str) -> bool {
-    // Basic email validation logic
-    // This can be expanded for more robust validation 🤓
-    email.contains('@') && email.contains('.')
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::ProtocolParams;
+    use crate::test_utils::{install_syscalls, read_account, MockAccount, TEST_TIMESTAMP};
     use anchor_lang::solana_program::pubkey::Pubkey;
 
-    #[test]
-    fn test_register_advertiser_success() {
-        let program_id = Pubkey::new_unique();
+    struct Fixture {
+        state: MockAccount,
+        protocol_config: MockAccount,
+        advertiser: MockAccount,
+        stake: MockAccount,
+        authority: MockAccount,
+        system_program: MockAccount,
+    }
+
+    fn create_mock_accounts() -> Fixture {
+        let program_id = crate::ID;
         let authority_pubkey = Pubkey::new_unique();
         let (state_pubkey, _) = Pubkey::find_program_address(&[b"state"], &program_id);
-        let (advertiser_pubkey, _) = Pubkey::find_program_address(&[b"advertiser", authority_pubkey.as_ref()], &program_id);
-
-        // Create mock accounts
-        let mut state_account = StateAccount::default();
-        state_account.bump = 255;
-        let mut state_data = state_account.try_to_vec().unwrap();
-        let mut state_lamports = 1000000000;
-        let state_account_info = AccountInfo::new(
-            &state_pubkey,
-            false,
-            true,
-            &mut state_lamports,
-            &mut state_data,
+        let (advertiser_pubkey, _) =
+            Pubkey::find_program_address(&[b"advertiser", authority_pubkey.as_ref()], &program_id);
+        let (protocol_config_pubkey, _) =
+            Pubkey::find_program_address(&[b"protocol_config"], &program_id);
+        let (stake_pubkey, _) = Pubkey::find_program_address(
+            &[b"advertiser_stake", advertiser_pubkey.as_ref()],
             &program_id,
-            false,
-            0,
         );
 
-        let mut advertiser_data = vec![0; AdvertiserAccount::SPACE];
-        let mut advertiser_lamports = 0;
-        let advertiser_account_info = AccountInfo::new(
-            &advertiser_pubkey,
-            false,
-            true,
-            &mut advertiser_lamports,
-            &mut advertiser_data,
-            &program_id,
-            false,
-            0,
-        );
+        let state_account = StateAccount {
+            bump: 255,
+            ..StateAccount::default()
+        };
+        let protocol_config = ProtocolConfig {
+            params: ProtocolParams::default(),
+            bump: 253,
+            ..ProtocolConfig::default()
+        };
 
-        let mut authority_lamports = MIN_DEPOSIT_AMOUNT;
-        let mut authority_data = vec![];
-        let authority_account_info = AccountInfo::new(
-            &authority_pubkey,
-            true,
-            false,
-            &mut authority_lamports,
-            &mut authority_data,
-            &Pubkey::default(),
-            false,
-            0,
-        );
+        // The authority holds exactly the stake
+        let mut authority = MockAccount::signer(authority_pubkey);
+        authority.lamports = protocol_config.params.advertiser_stake;
 
-        let system_program_id = Pubkey::new_unique();
-        let mut system_program_lamports = 0;
-        let mut system_program_data = vec![];
-        let system_program_account_info = AccountInfo::new(
-            &system_program_id,
-            false,
-            false,
-            &mut system_program_lamports,
-            &mut system_program_data,
-            &Pubkey::default(),
-            false,
-            0,
-        );
+        Fixture {
+            state: MockAccount::program_account(state_pubkey, &state_account, StateAccount::SPACE),
+            protocol_config: MockAccount {
+                is_writable: false,
+                ..MockAccount::program_account(
+                    protocol_config_pubkey,
+                    &protocol_config,
+                    ProtocolConfig::SPACE,
+                )
+            },
+            advertiser: MockAccount::init(advertiser_pubkey, AdvertiserAccount::SPACE),
+            stake: MockAccount::init(stake_pubkey, AdvertiserStake::SPACE),
+            authority,
+            system_program: MockAccount::program(system_program::ID),
+        }
+    }
+
+    fn register(fixture: &mut Fixture, name: &str, email_commitment: [u8; 32]) -> Result<()> {
+        install_syscalls();
+        let program_id = crate::ID;
+        let state_account_info = fixture.state.info();
+        let protocol_config_account_info = fixture.protocol_config.info();
+        let advertiser_account_info = fixture.advertiser.info();
+        let stake_account_info = fixture.stake.info();
+        let authority_account_info = fixture.authority.info();
+        let system_program_account_info = fixture.system_program.info();
 
-        let accounts = RegisterAdvertiser {
+        let mut accounts = RegisterAdvertiser {
             state: Account::try_from(&state_account_info).unwrap(),
-            advertiser: Account::try_from(&advertiser_account_info).unwrap(),
+            protocol_config: Account::try_from(&protocol_config_account_info).unwrap(),
+            advertiser: Account::try_from_unchecked(&advertiser_account_info).unwrap(),
+            stake: Account::try_from_unchecked(&stake_account_info).unwrap(),
             authority: Signer::try_from(&authority_account_info).unwrap(),
             system_program: Program::try_from(&system_program_account_info).unwrap(),
         };
+        let context = Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            RegisterAdvertiserBumps {
+                advertiser: 254,
+                stake: 253,
+            },
+        );
 
-        let name = "Test Advertiser".to_string();
-        let email = "test@example.com".to_string();
+        handler(context, name.to_string(), email_commitment)?;
+        accounts.exit(&program_id)
+    }
 
-        let mut context = Context::new(
-            program_id,
-            accounts,
-            &[&state_account_info, &advertiser_account_info, &authority_account_info, &system_program_account_info],
-            BTreeMap::new(),
-            BTreeMap::new(),
-        );
+    #[test]
+    fn test_register_advertiser_success() {
+        let mut fixture = create_mock_accounts();
+        let authority_pubkey = fixture.authority.key;
+        let name = "Test Advertiser";
+        let email_commitment =
+            AdvertiserAccount::email_commitment(&authority_pubkey, &[7; 32], "test@example.com")
+                .unwrap();
 
-        let result = handler(context, name.clone(), email.clone());
-        assert!(result.is_ok());
+        register(&mut fixture, name, email_commitment).unwrap();
 
         // Verify state account updates
-        let updated_state = StateAccount::try_from_slice(&state_account_info.data.borrow()).unwrap();
+        let updated_state: StateAccount = read_account(&fixture.state.info());
         assert_eq!(updated_state.advertiser_count, 1);
+        assert_eq!(updated_state.event_sequence, 1);
 
         // Verify advertiser account
-        let advertiser = AdvertiserAccount::try_from_slice(&advertiser_account_info.data.borrow()).unwrap();
+        let advertiser: AdvertiserAccount = read_account(&fixture.advertiser.info());
         assert_eq!(advertiser.authority, authority_pubkey);
         assert_eq!(advertiser.name, name);
-        assert_eq!(advertiser.email, email);
+        assert_eq!(advertiser.email_commitment, email_commitment);
+        assert!(!advertiser.is_email_verified());
         assert_eq!(advertiser.ad_count, 0);
         assert_eq!(advertiser.total_budget, 0);
-        assert_eq!(advertiser.reputation_score, 100);
+        assert_eq!(advertiser.reputation_score, INITIAL_REPUTATION);
+        assert_eq!(advertiser.reputation_updated_at, advertiser.created_at);
         assert!(advertiser.is_active);
-        assert!(advertiser.created_at > 0);
+        assert_eq!(advertiser.created_at, TEST_TIMESTAMP);
         assert_eq!(advertiser.last_updated, advertiser.created_at);
+        assert_eq!(advertiser.bump, 254);
+
+        // Verify the stake
+        let stake: AdvertiserStake = read_account(&fixture.stake.info());
+        assert_eq!(stake.advertiser, fixture.advertiser.key);
+        assert_eq!(stake.amount, ProtocolParams::default().advertiser_stake);
+        assert_eq!(stake.unlocks_at, 0);
+        assert_eq!(stake.bump, 253);
     }
 
     #[test]
     fn test_register_advertiser_invalid_name() {
-        // Similar setup as success test, but with an empty name
-        // Assert that the handler returns an error
+        let mut fixture = create_mock_accounts();
+
+        assert_eq!(
+            register(&mut fixture, "", [1; 32]).unwrap_err(),
+            ErrorCode::InvalidAdvertiserName.into()
+        );
     }
 
     #[test]
     fn test_register_advertiser_invalid_email() {
-        // Similar setup as success test, but with an invalid email
-        // Assert that the handler returns an error
+        let mut fixture = create_mock_accounts();
+
+        // An all-zero commitment is what an unset field looks like
+        assert_eq!(
+            register(&mut fixture, "Test Advertiser", [0; 32]).unwrap_err(),
+            ErrorCode::InvalidAdvertiserEmail.into()
+        );
     }
 
     #[test]
     fn test_register_advertiser_insufficient_funds() {
-        // Similar setup as success test, but with insufficient lamports in authority account
-        // Assert that the handler returns an error
-    }
-
-🏗️
+        let mut fixture = create_mock_accounts();
+        fixture.authority.lamports -= 1;
 
+        assert_eq!(
+            register(&mut fixture, "Test Advertiser", [1; 32]).unwrap_err(),
+            ErrorCode::InsufficientFunds.into()
+        );
+        let state: StateAccount = read_account(&fixture.state.info());
+        assert_eq!(state.advertiser_count, 0);
+    }
 }
@@ -0,0 +1,50 @@
+use crate::error::ErrorCode;
+use crate::events::PublisherRegistered;
+use crate::state::{PublisherAccount, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(publisher: Pubkey)]
+pub struct RegisterPublisher<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PublisherAccount::SPACE,
+        seeds = [b"publisher", publisher.as_ref()],
+        bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<RegisterPublisher>, publisher: Pubkey) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let publisher_account = &mut ctx.accounts.publisher_account;
+
+    publisher_account.authority = publisher;
+    publisher_account.created_at = Clock::get()?.unix_timestamp;
+    publisher_account.last_updated = publisher_account.created_at;
+    publisher_account.bump = ctx.bumps.publisher_account;
+
+    emit!(PublisherRegistered {
+        sequence: state.next_event_sequence()?,
+        publisher,
+        publisher_account: publisher_account.key(),
+        timestamp: publisher_account.created_at,
+    });
+
+    msg!("Publisher registered: {}", publisher);
+    Ok(())
+}
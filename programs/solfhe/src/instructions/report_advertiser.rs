@@ -0,0 +1,68 @@
+use crate::error::ErrorCode;
+use crate::events::ReputationUpdated;
+use crate::instructions::deactivate_advertiser::deactivate;
+use crate::reputation::ReputationReport;
+use crate::state::{AdvertiserAccount, AdvertiserStake, ProtocolConfig, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ReportAdvertiser<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser_stake", advertiser.key().as_ref()],
+        bump = stake.bump,
+        has_one = advertiser,
+    )]
+    pub stake: Account<'info, AdvertiserStake>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Lowers the advertiser's reputation for a flagged ad or a fraud report.
+/// An advertiser whose score reaches zero is deactivated.
+pub fn handler(ctx: Context<ReportAdvertiser>, report: ReputationReport) -> Result<()> {
+    let params = ctx.accounts.protocol_config.params;
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+    let now = Clock::get()?.unix_timestamp;
+
+    advertiser.refresh_reputation(now, params.reputation_half_life);
+    let was_active = advertiser.is_active;
+    advertiser.penalize(params.report_penalty(report));
+    advertiser.last_updated = now;
+
+    emit!(ReputationUpdated {
+        sequence: state.next_event_sequence()?,
+        advertiser: advertiser.key(),
+        reason: report.into(),
+        reputation_score: advertiser.reputation_score,
+        timestamp: now,
+    });
+
+    if was_active && !advertiser.is_active {
+        deactivate(
+            state,
+            advertiser,
+            &mut ctx.accounts.stake,
+            params.stake_cooldown,
+            now,
+        )?;
+    }
+
+    msg!("Advertiser reported: {:?}", report);
+    Ok(())
+}
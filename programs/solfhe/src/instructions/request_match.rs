@@ -0,0 +1,138 @@
+use crate::error::ErrorCode;
+use crate::events::MatchRequested;
+use crate::state::{
+    AdAccount, AdvertiserAccount, FheKeyRegistry, MatchRequest, ProtocolConfig, StateAccount,
+    UserProfile, MAX_MATCH_CANDIDATES,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RequestMatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"fhe_key_registry"], bump = fhe_key_registry.bump)]
+    pub fhe_key_registry: Account<'info, FheKeyRegistry>,
+
+    #[account(
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump,
+        has_one = user @ ErrorCode::Unauthorized,
+        constraint = user_profile.key_epoch == fhe_key_registry.epoch @ ErrorCode::StaleKeyEpoch,
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + MatchRequest::SPACE,
+        seeds = [b"match_request", user.key().as_ref()],
+        bump
+    )]
+    pub match_request: Account<'info, MatchRequest>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    // remaining_accounts: each candidate `AdAccount`, followed by its
+    // advertiser's `AdvertiserAccount`
+}
+
+pub fn handler(ctx: Context<RequestMatch>, timeout: i64) -> Result<()> {
+    ctx.accounts
+        .protocol_config
+        .params
+        .check_match_timeout(timeout)?;
+
+    let candidates = ctx.remaining_accounts.chunks_exact(2);
+    require!(
+        candidates.remainder().is_empty()
+            && candidates.len() > 0
+            && candidates.len() <= MAX_MATCH_CANDIDATES,
+        ErrorCode::InvalidMatchCandidates
+    );
+
+    let state = &mut ctx.accounts.state;
+    let user_profile = &ctx.accounts.user_profile;
+    let match_request = &mut ctx.accounts.match_request;
+    let now = Clock::get()?.unix_timestamp;
+
+    // Only one request per user may be pending at a time
+    require!(
+        match_request.user == Pubkey::default() || match_request.is_expired(now),
+        ErrorCode::MatchRequestPending
+    );
+
+    // Every candidate must be a distinct ad of an active advertiser that is
+    // running now and that the coprocessor can score against this profile
+    let mut candidate_ads = Vec::with_capacity(candidates.len());
+    for pair in candidates {
+        let (candidate, advertiser_info) = (&pair[0], &pair[1]);
+        require_keys_eq!(
+            *candidate.owner,
+            crate::ID,
+            ErrorCode::InvalidMatchCandidates
+        );
+        require_keys_eq!(
+            *advertiser_info.owner,
+            crate::ID,
+            ErrorCode::InvalidMatchCandidates
+        );
+        let ad = AdAccount::try_deserialize(&mut &candidate.try_borrow_data()?[..])?;
+        let advertiser =
+            AdvertiserAccount::try_deserialize(&mut &advertiser_info.try_borrow_data()?[..])?;
+
+        require_keys_eq!(
+            *advertiser_info.key,
+            ad.advertiser,
+            ErrorCode::InvalidMatchCandidates
+        );
+        require!(advertiser.is_active, ErrorCode::AdvertiserNotActive);
+        require!(ad.is_running(now)?, ErrorCode::AdNotActive);
+        require!(
+            ad.key_epoch == user_profile.key_epoch,
+            ErrorCode::StaleKeyEpoch
+        );
+        require!(
+            ad.trait_schema == user_profile.trait_schema,
+            ErrorCode::TraitSchemaMismatch
+        );
+        require!(
+            !candidate_ads.contains(candidate.key),
+            ErrorCode::InvalidMatchCandidates
+        );
+        candidate_ads.push(*candidate.key);
+    }
+
+    match_request.user = ctx.accounts.user.key();
+    match_request.user_profile = user_profile.key();
+    match_request.candidate_ads = candidate_ads;
+    match_request.key_epoch = user_profile.key_epoch;
+    match_request.created_at = now;
+    match_request.expires_at = now.checked_add(timeout).ok_or(ErrorCode::Overflow)?;
+    match_request.bump = ctx.bumps.match_request;
+
+    emit!(MatchRequested {
+        sequence: state.next_event_sequence()?,
+        user: match_request.user,
+        match_request: match_request.key(),
+        candidate_ads: match_request.candidate_ads.clone(),
+        expires_at: match_request.expires_at,
+        timestamp: now,
+    });
+
+    msg!(
+        "Match requested for {} candidate ads",
+        match_request.candidate_ads.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use crate::error::ErrorCode;
+use crate::events::AdResumed;
+use crate::state::{AdAccount, AdvertiserAccount, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ResumeAd<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = advertiser.is_active @ ErrorCode::AdvertiserNotActive,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(mut, has_one = advertiser @ ErrorCode::Unauthorized)]
+    pub ad: Account<'info, AdAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<ResumeAd>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let ad = &mut ctx.accounts.ad;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(!ad.is_active, ErrorCode::AdAlreadyActive);
+    require!(!ad.pending_review, ErrorCode::AdPendingReview);
+    require!(now < ad.ends_at()?, ErrorCode::AdExpired);
+    // A campaign ad's budget is checked against the campaign on settlement
+    require!(
+        ad.is_campaign_ad() || ad.remaining_budget()? > 0,
+        ErrorCode::InsufficientAdBudget
+    );
+
+    ad.is_active = true;
+    ad.last_updated = now;
+
+    emit!(AdResumed {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: ad.advertiser,
+        timestamp: now,
+    });
+
+    msg!("Ad resumed: {}", ad.key());
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::error::ErrorCode;
+use crate::events::ProtocolResumed;
+use crate::state::StateAccount;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ResumeProtocol<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<ResumeProtocol>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+
+    require!(state.is_paused, ErrorCode::ProtocolNotPaused);
+
+    state.is_paused = false;
+    state.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(ProtocolResumed {
+        sequence: state.next_event_sequence()?,
+        timestamp: state.last_updated,
+    });
+
+    msg!("Protocol resumed");
+    Ok(())
+}
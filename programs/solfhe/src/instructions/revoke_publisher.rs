@@ -0,0 +1,41 @@
+use crate::error::ErrorCode;
+use crate::events::PublisherRevoked;
+use crate::state::{PublisherAccount, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RevokePublisher<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"publisher", publisher_account.authority.as_ref()],
+        bump = publisher_account.bump,
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<RevokePublisher>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let publisher_account = &ctx.accounts.publisher_account;
+
+    emit!(PublisherRevoked {
+        sequence: state.next_event_sequence()?,
+        publisher: publisher_account.authority,
+        publisher_account: publisher_account.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Publisher revoked: {}", publisher_account.authority);
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use crate::error::ErrorCode;
+use crate::events::FheKeysRotated;
+use crate::state::{FheKeyParams, FheKeyRegistry, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RotateFheKeys<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"fhe_key_registry"],
+        bump = fhe_key_registry.bump,
+    )]
+    pub fhe_key_registry: Account<'info, FheKeyRegistry>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<RotateFheKeys>, fhe_keys: FheKeyParams) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let fhe_key_registry = &mut ctx.accounts.fhe_key_registry;
+
+    // Ciphertexts from earlier epochs stop being matched once the epoch moves
+    fhe_key_registry.set_keys(fhe_keys, Clock::get()?.unix_timestamp, true)?;
+
+    emit!(FheKeysRotated {
+        sequence: state.next_event_sequence()?,
+        epoch: fhe_key_registry.epoch,
+        public_key_hash: fhe_key_registry.public_key_hash,
+        server_key_hash: fhe_key_registry.server_key_hash,
+        key_uri: fhe_key_registry.key_uri.clone(),
+        parameter_set: fhe_key_registry.parameter_set,
+        timestamp: fhe_key_registry.rotated_at,
+    });
+
+    msg!("FHE keys rotated to epoch {}", fhe_key_registry.epoch);
+    Ok(())
+}
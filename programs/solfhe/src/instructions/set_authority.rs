@@ -0,0 +1,36 @@
+use crate::error::ErrorCode;
+use crate::events::AuthorityProposed;
+use crate::state::StateAccount;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Proposes `new_authority`, which takes over once it signs
+/// `accept_authority`. Proposing `Pubkey::default()` cancels a pending handover.
+pub fn handler(ctx: Context<SetAuthority>, new_authority: Pubkey) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+
+    state.pending_authority = new_authority;
+    state.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(AuthorityProposed {
+        sequence: state.next_event_sequence()?,
+        authority: state.authority,
+        pending_authority: new_authority,
+        timestamp: state.last_updated,
+    });
+
+    msg!("Authority proposed: {}", new_authority);
+    Ok(())
+}
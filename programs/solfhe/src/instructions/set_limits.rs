@@ -0,0 +1,32 @@
+use crate::error::ErrorCode;
+use crate::instructions::update_protocol_config::write_params;
+use crate::state::{AdLimits, ProtocolConfig, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetLimits<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(mut, seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Updates only the ad limits of the protocol config. Existing ads keep their
+/// content and duration; the new limits apply to later instructions.
+pub fn handler(ctx: Context<SetLimits>, limits: AdLimits) -> Result<()> {
+    let accounts = ctx.accounts;
+    let mut params = accounts.protocol_config.params;
+    limits.apply_to(&mut params);
+    write_params(&mut accounts.state, &mut accounts.protocol_config, params)?;
+
+    msg!("Ad limits updated");
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use crate::error::ErrorCode;
+use crate::instructions::update_protocol_config::write_params;
+use crate::state::{ProtocolConfig, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetMatchThreshold<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(mut, seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Updates only `ProtocolParams::match_threshold`.
+pub fn handler(ctx: Context<SetMatchThreshold>, match_threshold: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    let mut params = accounts.protocol_config.params;
+    params.match_threshold = match_threshold;
+    write_params(&mut accounts.state, &mut accounts.protocol_config, params)?;
+
+    msg!("Match threshold set to {}", match_threshold);
+    Ok(())
+}
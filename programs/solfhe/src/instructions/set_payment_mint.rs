@@ -0,0 +1,44 @@
+use crate::error::ErrorCode;
+use crate::events::PaymentMintSet;
+use crate::state::StateAccount;
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+#[derive(Accounts)]
+pub struct SetPaymentMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    pub payment_mint: Account<'info, Mint>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetPaymentMint>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+
+    // The primary mint's vaults and user rewards are derived without a mint
+    // seed, so it can only be set once, before any of them exist. Further
+    // mints are accepted with add_payment_mint.
+    require!(
+        state.payment_mint == Pubkey::default(),
+        ErrorCode::PaymentMintAlreadySet
+    );
+
+    state.payment_mint = ctx.accounts.payment_mint.key();
+    state.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(PaymentMintSet {
+        sequence: state.next_event_sequence()?,
+        mint: state.payment_mint,
+        timestamp: state.last_updated,
+    });
+
+    msg!("Payment mint set: {}", state.payment_mint);
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::error::ErrorCode;
+use crate::events::ProtocolFeeUpdated;
+use crate::state::{StateAccount, MAX_BPS};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetProtocolFee<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetProtocolFee>, protocol_fee_bps: u16) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+
+    // Settlements are split between the fee, the user and the publisher
+    require!(
+        u32::from(protocol_fee_bps) + u32::from(state.user_reward_bps) <= u32::from(MAX_BPS),
+        ErrorCode::InvalidBasisPoints
+    );
+
+    state.protocol_fee_bps = protocol_fee_bps;
+    state.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(ProtocolFeeUpdated {
+        sequence: state.next_event_sequence()?,
+        protocol_fee_bps,
+        timestamp: state.last_updated,
+    });
+
+    msg!("Protocol fee set to {} bps", protocol_fee_bps);
+    Ok(())
+}
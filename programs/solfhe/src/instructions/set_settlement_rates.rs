@@ -0,0 +1,48 @@
+use crate::error::ErrorCode;
+use crate::events::SettlementRatesUpdated;
+use crate::state::{StateAccount, MAX_BPS};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetSettlementRates<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<SetSettlementRates>,
+    cpm_rate: u64,
+    cpc_rate: u64,
+    user_reward_bps: u16,
+) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+
+    // Settlements are split between the fee, the user and the publisher
+    require!(
+        u32::from(user_reward_bps) + u32::from(state.protocol_fee_bps) <= u32::from(MAX_BPS),
+        ErrorCode::InvalidBasisPoints
+    );
+
+    state.cpm_rate = cpm_rate;
+    state.cpc_rate = cpc_rate;
+    state.user_reward_bps = user_reward_bps;
+    state.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(SettlementRatesUpdated {
+        sequence: state.next_event_sequence()?,
+        cpm_rate,
+        cpc_rate,
+        user_reward_bps,
+        timestamp: state.last_updated,
+    });
+
+    msg!("Settlement rates set: CPM {}, CPC {}", cpm_rate, cpc_rate);
+    Ok(())
+}
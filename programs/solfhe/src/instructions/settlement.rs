@@ -0,0 +1,294 @@
+//! Shared implementation of `record_impression` and `record_click`.
+
+use crate::error::ErrorCode;
+use crate::events::{AdDailyCapReached, AdPaused, CampaignPaused, ClickSettled, ImpressionSettled};
+use crate::state::{
+    bps_of, cpc_cost, cpm_cost, AdAccount, AdvertiserAccount, Campaign, MatchedAdsAccount,
+    PaymentMintRegistry, PublisherAccount, StateAccount, UserRewards,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+/// What a batch of settled events is counted as.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Settlement {
+    Impression,
+    Click,
+}
+
+/// A publisher reporting that `user` was shown, or clicked, an ad they were
+/// matched with. Only ads in the user's latest `MatchedAdsAccount` qualify.
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher.key().as_ref()],
+        bump = publisher_account.bump,
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(mut, constraint = ad.has_vault @ ErrorCode::AdVaultNotMigrated)]
+    pub ad: Account<'info, AdAccount>,
+
+    /// A deactivated advertiser's ads keep their flags but stop spending
+    #[account(
+        address = ad.advertiser @ ErrorCode::Unauthorized,
+        constraint = advertiser.is_active @ ErrorCode::AdvertiserNotActive,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    /// Required for an ad paid in a mint other than the primary one, whose
+    /// rates it holds
+    #[account(seeds = [b"payment_mint_registry"], bump = payment_mint_registry.bump)]
+    pub payment_mint_registry: Option<Account<'info, PaymentMintRegistry>>,
+
+    /// The ad's own vault, or its campaign's vault for a campaign ad
+    #[account(mut, address = ad.budget_vault(&ad.key())?)]
+    pub ad_vault: Account<'info, TokenAccount>,
+
+    /// Required for campaign ads, whose spend is charged to the campaign
+    #[account(mut, address = ad.campaign @ ErrorCode::CampaignMismatch)]
+    pub campaign: Option<Account<'info, Campaign>>,
+
+    #[account(
+        mut,
+        constraint = publisher_token_account.owner == publisher.key(),
+        constraint = publisher_token_account.mint == ad.mint,
+    )]
+    pub publisher_token_account: Account<'info, TokenAccount>,
+
+    /// Holds user rewards in the ad's mint until they are claimed
+    #[account(
+        mut,
+        constraint = rewards_vault.mint == ad.mint,
+        seeds = [b"rewards_vault", state.mint_seed(&ad.mint)],
+        bump
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.mint == ad.mint,
+        seeds = [b"fee_vault", state.mint_seed(&ad.mint)],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: only used to derive the user's PDAs
+    pub user: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"matched_ads", user.key().as_ref()],
+        bump = matched_ads.bump,
+        constraint = matched_ads.contains(&ad.key()) @ ErrorCode::AdNotMatched,
+    )]
+    pub matched_ads: Account<'info, MatchedAdsAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = publisher,
+        space = 8 + UserRewards::SPACE,
+        seeds = [b"user_rewards", user.key().as_ref(), state.mint_seed(&ad.mint)],
+        bump
+    )]
+    pub user_rewards: Account<'info, UserRewards>,
+
+    #[account(mut)]
+    pub publisher: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Settle<'info> {
+    /// Transfers `amount` out of the ad's vault, or its campaign's, which the
+    /// state PDA owns.
+    fn pay_from_vault(&self, to: &Account<'info, TokenAccount>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let state_bump = [self.state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"state", &state_bump]];
+        let cpi_accounts = Transfer {
+            from: self.ad_vault.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.state.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token::transfer(cpi_ctx, amount)
+    }
+}
+
+pub fn settle(ctx: Context<Settle>, kind: Settlement, count: u32) -> Result<()> {
+    require!(count > 0, ErrorCode::InvalidSettlementCount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let state = &ctx.accounts.state;
+    let (cpm_rate, cpc_rate) = state.settlement_rates(
+        &ctx.accounts.ad.mint,
+        ctx.accounts.payment_mint_registry.as_deref(),
+    )?;
+    let cost = match kind {
+        Settlement::Impression => cpm_cost(cpm_rate, count)?,
+        Settlement::Click => cpc_cost(cpc_rate, count)?,
+    };
+    let user_reward_bps = state.user_reward_bps;
+
+    let ad = &mut ctx.accounts.ad;
+    require!(ad.is_active, ErrorCode::AdNotActive);
+    require!(now >= ad.start_at, ErrorCode::AdNotStarted);
+    require!(now < ad.ends_at()?, ErrorCode::AdExpired);
+    require!(
+        ad.is_campaign_ad() == ctx.accounts.campaign.is_some(),
+        ErrorCode::CampaignMismatch
+    );
+
+    // Pace what is left of the ad's budget, or of its campaign's
+    let unspent = match ctx.accounts.campaign.as_ref() {
+        Some(campaign) => {
+            require!(campaign.is_active, ErrorCode::CampaignNotActive);
+            require!(now >= campaign.start_at, ErrorCode::CampaignNotStarted);
+            require!(now < campaign.ends_at()?, ErrorCode::CampaignExpired);
+            campaign.remaining_budget()?
+        }
+        None => ad.remaining_budget()?,
+    };
+    let daily_limit = ad.daily_limit(unspent, now)?;
+    let allowance = ad.daily_allowance(unspent, now)?;
+    require!(allowance > 0, ErrorCode::DailyCapReached);
+    let cost = cost.min(allowance);
+
+    // The last batch may only be partly covered by the remaining budget or
+    // the day's allowance
+    let charged = match ctx.accounts.campaign.as_mut() {
+        Some(campaign) => {
+            let charged = campaign.charge(cost)?;
+            ad.draw(charged)?;
+
+            let campaign_count = match kind {
+                Settlement::Impression => &mut campaign.impressions,
+                Settlement::Click => &mut campaign.clicks,
+            };
+            *campaign_count = campaign_count
+                .checked_add(u64::from(count))
+                .ok_or(ErrorCode::Overflow)?;
+            campaign.last_updated = now;
+            charged
+        }
+        None => ad.charge(cost)?,
+    };
+    ad.record_daily_spend(charged, now)?;
+    let ad_count = match kind {
+        Settlement::Impression => &mut ad.impressions,
+        Settlement::Click => &mut ad.clicks,
+    };
+    *ad_count = ad_count
+        .checked_add(u64::from(count))
+        .ok_or(ErrorCode::Overflow)?;
+    ad.last_updated = now;
+
+    // Split the charge between the protocol, the user and the publisher. The
+    // user's share waits in the rewards vault until claimed.
+    let protocol_fee = ctx.accounts.state.collect_fee(charged)?;
+    let user_reward = bps_of(charged, user_reward_bps);
+    let publisher_payout = charged - protocol_fee - user_reward;
+    ctx.accounts
+        .pay_from_vault(&ctx.accounts.fee_vault, protocol_fee)?;
+    ctx.accounts
+        .pay_from_vault(&ctx.accounts.rewards_vault, user_reward)?;
+    ctx.accounts
+        .pay_from_vault(&ctx.accounts.publisher_token_account, publisher_payout)?;
+
+    let user_rewards = &mut ctx.accounts.user_rewards;
+    if user_rewards.user == Pubkey::default() {
+        user_rewards.user = ctx.accounts.user.key();
+        user_rewards.mint = ctx.accounts.ad.mint;
+        user_rewards.created_at = now;
+        user_rewards.bump = ctx.bumps.user_rewards;
+    }
+    user_rewards.accrue(user_reward, now)?;
+
+    let publisher_account = &mut ctx.accounts.publisher_account;
+    let publisher_count = match kind {
+        Settlement::Impression => &mut publisher_account.impressions,
+        Settlement::Click => &mut publisher_account.clicks,
+    };
+    *publisher_count = publisher_count
+        .checked_add(u64::from(count))
+        .ok_or(ErrorCode::Overflow)?;
+    publisher_account.earned = publisher_account
+        .earned
+        .checked_add(publisher_payout)
+        .ok_or(ErrorCode::Overflow)?;
+    publisher_account.last_updated = now;
+
+    let ad = &ctx.accounts.ad;
+    let state = &mut ctx.accounts.state;
+    let sequence = state.next_event_sequence()?;
+    match kind {
+        Settlement::Impression => emit!(ImpressionSettled {
+            sequence,
+            ad: ad.key(),
+            publisher: publisher_account.authority,
+            user: user_rewards.user,
+            count,
+            cost: charged,
+            protocol_fee,
+            user_reward,
+            spent_budget: ad.spent_budget,
+            impressions: ad.impressions,
+            timestamp: now,
+        }),
+        Settlement::Click => emit!(ClickSettled {
+            sequence,
+            ad: ad.key(),
+            publisher: publisher_account.authority,
+            user: user_rewards.user,
+            count,
+            cost: charged,
+            protocol_fee,
+            user_reward,
+            spent_budget: ad.spent_budget,
+            clicks: ad.clicks,
+            timestamp: now,
+        }),
+    }
+    if ad.is_active && ad.day_spent >= daily_limit {
+        emit!(AdDailyCapReached {
+            sequence: state.next_event_sequence()?,
+            ad: ad.key(),
+            advertiser: ad.advertiser,
+            day_spent: ad.day_spent,
+            resets_at: ad.next_spend_day_at(),
+            timestamp: now,
+        });
+    }
+    if !ad.is_active {
+        emit!(AdPaused {
+            sequence: state.next_event_sequence()?,
+            ad: ad.key(),
+            advertiser: ad.advertiser,
+            timestamp: now,
+        });
+    }
+    if let Some(campaign) = ctx.accounts.campaign.as_ref().filter(|c| !c.is_active) {
+        emit!(CampaignPaused {
+            sequence: state.next_event_sequence()?,
+            campaign: campaign.key(),
+            advertiser: campaign.advertiser,
+            timestamp: now,
+        });
+    }
+
+    msg!("Settled {} events for {} tokens", count, charged);
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use crate::error::ErrorCode;
+use crate::events::AdvertiserSlashed;
+use crate::instructions::deactivate_advertiser::deactivate;
+use crate::state::{AdvertiserAccount, AdvertiserStake, ProtocolConfig, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SlashAdvertiser<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser_stake", advertiser.key().as_ref()],
+        bump = stake.bump,
+        has_one = advertiser,
+    )]
+    pub stake: Account<'info, AdvertiserStake>,
+
+    /// Receives the slashed lamports
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Penalizes a policy violation: takes `amount` lamports of stake and lowers
+/// the reputation score by `reputation_penalty`. An advertiser whose score
+/// reaches zero is deactivated and its stake cooldown starts.
+pub fn handler(ctx: Context<SlashAdvertiser>, amount: u64, reputation_penalty: u16) -> Result<()> {
+    require!(
+        amount > 0 || reputation_penalty > 0,
+        ErrorCode::InvalidAmount
+    );
+    require!(
+        amount <= ctx.accounts.stake.amount,
+        ErrorCode::InsufficientFunds
+    );
+
+    // The stake account is owned by the program, so lamports move directly
+    ctx.accounts.stake.sub_lamports(amount)?;
+    ctx.accounts.authority.add_lamports(amount)?;
+
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+    let stake = &mut ctx.accounts.stake;
+    let now = Clock::get()?.unix_timestamp;
+
+    stake.amount -= amount;
+    stake.total_slashed = stake
+        .total_slashed
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    stake.last_updated = now;
+
+    let params = ctx.accounts.protocol_config.params;
+    advertiser.refresh_reputation(now, params.reputation_half_life);
+    let was_active = advertiser.is_active;
+    advertiser.penalize(reputation_penalty);
+    advertiser.last_updated = now;
+
+    emit!(AdvertiserSlashed {
+        sequence: state.next_event_sequence()?,
+        advertiser: advertiser.key(),
+        amount,
+        reputation_penalty,
+        reputation_score: advertiser.reputation_score,
+        timestamp: now,
+    });
+
+    if was_active && !advertiser.is_active {
+        deactivate(state, advertiser, stake, params.stake_cooldown, now)?;
+    }
+
+    msg!(
+        "Advertiser slashed: {} lamports, {} reputation",
+        amount,
+        reputation_penalty
+    );
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use crate::envelope::CiphertextEnvelope;
+use crate::error::ErrorCode;
+use crate::events::UserProfileSubmitted;
+use crate::state::{FheKeyRegistry, StateAccount, UserProfile, MAX_PROFILE_DATA_SIZE};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+
+// Domain separator for the message users sign over their profile
+const PROFILE_SIGNING_DOMAIN: &[u8] = b"solfhe:user_profile:v1";
+
+// Layout of the native Ed25519 program instruction data
+const ED25519_HEADER_SIZE: usize = 2;
+const ED25519_OFFSETS_SIZE: usize = 14;
+const ED25519_PUBKEY_SIZE: usize = 32;
+const ED25519_SIGNATURE_SIZE: usize = 64;
+
+#[derive(Accounts)]
+pub struct SubmitUserProfile<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"fhe_key_registry"], bump = fhe_key_registry.bump)]
+    pub fhe_key_registry: Account<'info, FheKeyRegistry>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::SPACE,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: constrained to the instructions sysvar, read via introspection
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<SubmitUserProfile>,
+    encrypted_profile_data: CiphertextEnvelope,
+) -> Result<()> {
+    // Validate the envelope against the network keys and bound its size
+    let stored_profile =
+        encrypted_profile_data.validate(&ctx.accounts.fhe_key_registry, MAX_PROFILE_DATA_SIZE)?;
+
+    let state = &mut ctx.accounts.state;
+    let user_profile = &mut ctx.accounts.user_profile;
+    let user = &ctx.accounts.user;
+
+    // The user must have signed the profile in a preceding Ed25519 instruction
+    let message = profile_signing_message(&stored_profile);
+    verify_ed25519_instruction(&ctx.accounts.instructions, &user.key(), &message)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let is_new = user_profile.user == Pubkey::default();
+
+    if is_new {
+        user_profile.user = user.key();
+        user_profile.created_at = now;
+        user_profile.bump = ctx.bumps.user_profile;
+
+        state.user_count = state.user_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        state.last_updated = now;
+    }
+
+    user_profile.encrypted_data = stored_profile;
+    user_profile.key_epoch = encrypted_profile_data.key_epoch;
+    user_profile.trait_schema = encrypted_profile_data.trait_schema;
+    user_profile.last_updated = now;
+
+    emit!(UserProfileSubmitted {
+        sequence: state.next_event_sequence()?,
+        user: user.key(),
+        user_profile: user_profile.key(),
+        key_epoch: user_profile.key_epoch,
+        is_new,
+        timestamp: now,
+    });
+
+    msg!("User profile submitted and verified: {}", user.key());
+    Ok(())
+}
+
+/// Message the user signs with the Ed25519 program: a domain-separated hash
+/// of the serialized profile envelope.
+pub fn profile_signing_message(serialized_profile: &[u8]) -> [u8; 32] {
+    hashv(&[PROFILE_SIGNING_DOMAIN, serialized_profile]).to_bytes()
+}
+
+// Checks that the instruction right before this one is a native Ed25519
+// verification of `message` by `signer`. The runtime has already verified the
+// signature itself; only what was verified needs checking here.
+fn verify_ed25519_instruction(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current_index = load_current_index_checked(instructions)?;
+    require!(current_index > 0, ErrorCode::MissingEd25519Instruction);
+
+    let ix = load_instruction_at_checked(current_index as usize - 1, instructions)?;
+    require!(
+        ix.program_id == ed25519_program::ID && ix.accounts.is_empty(),
+        ErrorCode::MissingEd25519Instruction
+    );
+
+    let (verified_pubkey, verified_message) = parse_ed25519_instruction(&ix.data)?;
+    require!(
+        verified_pubkey == signer.as_ref() && verified_message == message,
+        ErrorCode::InvalidProfileSignature
+    );
+
+    Ok(())
+}
+
+// Extracts the public key and message of a single-signature Ed25519
+// instruction whose data all lives inside the instruction itself.
+fn parse_ed25519_instruction(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    require!(
+        data.len() >= ED25519_HEADER_SIZE + ED25519_OFFSETS_SIZE && data[0] == 1,
+        ErrorCode::InvalidProfileSignature
+    );
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let offsets = ED25519_HEADER_SIZE;
+    let signature_offset = read_u16(offsets) as usize;
+    let signature_ix_index = read_u16(offsets + 2);
+    let pubkey_offset = read_u16(offsets + 4) as usize;
+    let pubkey_ix_index = read_u16(offsets + 6);
+    let message_offset = read_u16(offsets + 8) as usize;
+    let message_size = read_u16(offsets + 10) as usize;
+    let message_ix_index = read_u16(offsets + 12);
+
+    // u16::MAX means "this instruction"; anything else could point at data
+    // the user never signed over
+    require!(
+        signature_ix_index == u16::MAX
+            && pubkey_ix_index == u16::MAX
+            && message_ix_index == u16::MAX,
+        ErrorCode::InvalidProfileSignature
+    );
+    require!(
+        signature_offset + ED25519_SIGNATURE_SIZE <= data.len()
+            && pubkey_offset + ED25519_PUBKEY_SIZE <= data.len()
+            && message_offset + message_size <= data.len(),
+        ErrorCode::InvalidProfileSignature
+    );
+
+    Ok((
+        &data[pubkey_offset..pubkey_offset + ED25519_PUBKEY_SIZE],
+        &data[message_offset..message_offset + message_size],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds Ed25519 instruction data in the layout produced by
+    // `solana_sdk::ed25519_instruction::new_ed25519_instruction`.
+    fn ed25519_data(pubkey: &[u8], message: &[u8], ix_index: u16) -> Vec<u8> {
+        let pubkey_offset = ED25519_HEADER_SIZE + ED25519_OFFSETS_SIZE;
+        let signature_offset = pubkey_offset + ED25519_PUBKEY_SIZE;
+        let message_offset = signature_offset + ED25519_SIGNATURE_SIZE;
+
+        let mut data = vec![1, 0];
+        for value in [
+            signature_offset as u16,
+            ix_index,
+            pubkey_offset as u16,
+            ix_index,
+            message_offset as u16,
+            message.len() as u16,
+            ix_index,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(pubkey);
+        data.extend_from_slice(&[0; ED25519_SIGNATURE_SIZE]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn test_parse_ed25519_instruction() {
+        let pubkey = Pubkey::new_unique();
+        let message = profile_signing_message(b"profile");
+        let data = ed25519_data(pubkey.as_ref(), &message, u16::MAX);
+
+        let (parsed_pubkey, parsed_message) = parse_ed25519_instruction(&data).unwrap();
+        assert_eq!(parsed_pubkey, pubkey.as_ref());
+        assert_eq!(parsed_message, message);
+    }
+
+    #[test]
+    fn test_parse_ed25519_instruction_rejects_foreign_data() {
+        let pubkey = Pubkey::new_unique();
+        let message = profile_signing_message(b"profile");
+        let data = ed25519_data(pubkey.as_ref(), &message, 0);
+
+        assert_eq!(
+            parse_ed25519_instruction(&data).unwrap_err(),
+            ErrorCode::InvalidProfileSignature.into()
+        );
+    }
+
+    #[test]
+    fn test_parse_ed25519_instruction_rejects_truncated_data() {
+        let pubkey = Pubkey::new_unique();
+        let message = profile_signing_message(b"profile");
+        let mut data = ed25519_data(pubkey.as_ref(), &message, u16::MAX);
+        data.truncate(data.len() - 1);
+
+        assert_eq!(
+            parse_ed25519_instruction(&data).unwrap_err(),
+            ErrorCode::InvalidProfileSignature.into()
+        );
+    }
+
+    #[test]
+    fn test_profile_signing_message_is_domain_separated() {
+        assert_ne!(
+            profile_signing_message(b"profile"),
+            hashv(&[b"profile"]).to_bytes()
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::error::ErrorCode;
+use crate::events::{AdBudgetToppedUp, AdResumed, ProtocolFeeCollected};
+use crate::state::{
+    AdAccount, AdvertiserAccount, PaymentMintRegistry, ProtocolConfig, StateAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct TopUpAd<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Required for an ad paid in a mint other than the primary one
+    #[account(seeds = [b"payment_mint_registry"], bump = payment_mint_registry.bump)]
+    pub payment_mint_registry: Option<Account<'info, PaymentMintRegistry>>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = advertiser.is_active @ ErrorCode::AdvertiserNotActive,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    /// Campaign ads are funded through their campaign
+    #[account(
+        mut,
+        has_one = advertiser @ ErrorCode::Unauthorized,
+        constraint = ad.has_vault @ ErrorCode::AdVaultNotMigrated,
+        constraint = !ad.is_campaign_ad() @ ErrorCode::CampaignMismatch,
+    )]
+    pub ad: Account<'info, AdAccount>,
+
+    #[account(mut, seeds = [b"ad_vault", ad.key().as_ref()], bump = ad.vault_bump)]
+    pub ad_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = advertiser_token_account.owner == authority.key(),
+        constraint = advertiser_token_account.mint == ad.mint,
+    )]
+    pub advertiser_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.mint == ad.mint,
+        seeds = [b"fee_vault", state.mint_seed(&ad.mint)],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Adds `amount` to the ad's escrowed budget, less the protocol fee, keeping
+/// the ad's stats. An ad paused because its budget ran out runs again.
+pub fn handler(ctx: Context<TopUpAd>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    // A mint that no longer accepts deposits cannot be topped up either
+    let params = ctx.accounts.state.deposit_params(
+        &ctx.accounts.ad.mint,
+        &ctx.accounts.protocol_config.params,
+        ctx.accounts.payment_mint_registry.as_deref(),
+    )?;
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+    let ad = &mut ctx.accounts.ad;
+    let authority = &ctx.accounts.authority;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(now < ad.ends_at()?, ErrorCode::AdExpired);
+
+    // Top-ups count towards the same reputation cap as the initial deposit,
+    // both gross of the protocol fee
+    advertiser.refresh_reputation(now, params.reputation_half_life);
+    let deposited = ad
+        .deposited
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    params.check_budget_cap(advertiser.reputation_score, deposited)?;
+
+    require!(
+        ctx.accounts.advertiser_token_account.amount >= amount,
+        ErrorCode::InsufficientFunds
+    );
+
+    // The protocol fee comes out of the deposit, as in create_ad
+    let fee = state.collect_fee(amount)?;
+    let net_amount = amount - fee;
+
+    if fee > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.advertiser_token_account.to_account_info(),
+            to: ctx.accounts.fee_vault.to_account_info(),
+            authority: authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, fee)?;
+    }
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.advertiser_token_account.to_account_info(),
+        to: ctx.accounts.ad_vault.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    token::transfer(cpi_ctx, net_amount)?;
+
+    // Only ads paused by an exhausted budget resume; one the advertiser
+    // paused, or that waits for review, stays as it is
+    let budget_paused = !ad.is_active && !ad.pending_review && ad.remaining_budget()? == 0;
+
+    ad.budget = ad
+        .budget
+        .checked_add(net_amount)
+        .ok_or(ErrorCode::Overflow)?;
+    ad.deposited = deposited;
+    ad.last_updated = now;
+    let resumed = budget_paused && net_amount > 0;
+    if resumed {
+        ad.is_active = true;
+    }
+
+    advertiser.total_budget = advertiser
+        .total_budget
+        .checked_add(net_amount)
+        .ok_or(ErrorCode::Overflow)?;
+    advertiser.last_updated = now;
+    state.total_budget = state
+        .total_budget
+        .checked_add(net_amount)
+        .ok_or(ErrorCode::Overflow)?;
+    state.last_updated = now;
+
+    if fee > 0 {
+        emit!(ProtocolFeeCollected {
+            sequence: state.next_event_sequence()?,
+            ad: ad.key(),
+            campaign: None,
+            amount: fee,
+            timestamp: now,
+        });
+    }
+    emit!(AdBudgetToppedUp {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: advertiser.key(),
+        amount: net_amount,
+        budget: ad.budget,
+        timestamp: now,
+    });
+    if resumed {
+        emit!(AdResumed {
+            sequence: state.next_event_sequence()?,
+            ad: ad.key(),
+            advertiser: advertiser.key(),
+            timestamp: now,
+        });
+    }
+
+    msg!("Ad budget topped up by {} tokens", net_amount);
+    Ok(())
+}
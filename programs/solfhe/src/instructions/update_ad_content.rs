@@ -0,0 +1,192 @@
+use crate::error::ErrorCode;
+use crate::events::AdContentUpdated;
+use crate::reputation::ReputationTier;
+use crate::state::{AdAccount, AdvertiserAccount, ProtocolConfig, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateAdContent<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(mut, has_one = advertiser @ ErrorCode::Unauthorized)]
+    pub ad: Account<'info, AdAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Replaces an ad's creative. Ads of advertisers in the review tier go back
+/// to `approve_ad` before they run again.
+pub fn handler(ctx: Context<UpdateAdContent>, content: String) -> Result<()> {
+    let params = &ctx.accounts.protocol_config.params;
+    params.check_content(&content)?;
+    let now = Clock::get()?.unix_timestamp;
+
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+    let ad = &mut ctx.accounts.ad;
+
+    // New content is reviewed like a new ad would be
+    advertiser.refresh_reputation(now, params.reputation_half_life);
+    if params.reputation_tier(advertiser.reputation_score) == ReputationTier::Review {
+        ad.pending_review = true;
+        ad.is_active = false;
+    }
+
+    // Targeting traits are left untouched; only the creative changes
+    ad.content = content;
+    ad.last_updated = now;
+
+    emit!(AdContentUpdated {
+        sequence: state.next_event_sequence()?,
+        ad: ad.key(),
+        advertiser: ad.advertiser,
+        content: ad.content.clone(),
+        pending_review: ad.pending_review,
+        timestamp: ad.last_updated,
+    });
+
+    msg!("Ad content updated: {}", ad.key());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::ProtocolParams;
+    use crate::test_utils::{install_syscalls, read_account, MockAccount, TEST_TIMESTAMP};
+    use anchor_lang::solana_program::pubkey::Pubkey;
+
+    struct Fixture {
+        state: MockAccount,
+        protocol_config: MockAccount,
+        advertiser: MockAccount,
+        ad: MockAccount,
+        authority: MockAccount,
+    }
+
+    fn create_mock_accounts(reputation_score: u16) -> Fixture {
+        let program_id = crate::ID;
+        let authority_pubkey = Pubkey::new_unique();
+        let (advertiser_pubkey, _) =
+            Pubkey::find_program_address(&[b"advertiser", authority_pubkey.as_ref()], &program_id);
+
+        let state_account = StateAccount {
+            bump: 255,
+            ..StateAccount::default()
+        };
+        let protocol_config = ProtocolConfig {
+            params: ProtocolParams::default(),
+            bump: 253,
+            ..ProtocolConfig::default()
+        };
+        let advertiser = AdvertiserAccount {
+            authority: authority_pubkey,
+            reputation_score,
+            reputation_updated_at: TEST_TIMESTAMP,
+            is_active: true,
+            open_ad_count: 1,
+            bump: 254,
+            ..AdvertiserAccount::default()
+        };
+        let ad = AdAccount {
+            advertiser: advertiser_pubkey,
+            content: "Old content".to_string(),
+            budget: 100_000_000,
+            duration: 24 * 60 * 60,
+            start_at: TEST_TIMESTAMP,
+            created_at: TEST_TIMESTAMP,
+            is_active: true,
+            ..AdAccount::default()
+        };
+
+        Fixture {
+            state: MockAccount::program_account(
+                Pubkey::find_program_address(&[b"state"], &program_id).0,
+                &state_account,
+                StateAccount::SPACE,
+            ),
+            protocol_config: MockAccount::program_account(
+                Pubkey::find_program_address(&[b"protocol_config"], &program_id).0,
+                &protocol_config,
+                ProtocolConfig::SPACE,
+            ),
+            advertiser: MockAccount::program_account(
+                advertiser_pubkey,
+                &advertiser,
+                AdvertiserAccount::SPACE,
+            ),
+            ad: MockAccount::program_account(Pubkey::new_unique(), &ad, AdAccount::SPACE),
+            authority: MockAccount::signer(authority_pubkey),
+        }
+    }
+
+    fn update(fixture: &mut Fixture, content: &str) -> Result<()> {
+        install_syscalls();
+        let program_id = crate::ID;
+        let state_account_info = fixture.state.info();
+        let protocol_config_account_info = fixture.protocol_config.info();
+        let advertiser_account_info = fixture.advertiser.info();
+        let ad_account_info = fixture.ad.info();
+        let authority_account_info = fixture.authority.info();
+
+        let mut accounts = UpdateAdContent {
+            state: Account::try_from(&state_account_info).unwrap(),
+            protocol_config: Account::try_from(&protocol_config_account_info).unwrap(),
+            advertiser: Account::try_from(&advertiser_account_info).unwrap(),
+            ad: Account::try_from(&ad_account_info).unwrap(),
+            authority: Signer::try_from(&authority_account_info).unwrap(),
+        };
+        let context = Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateAdContentBumps::default(),
+        );
+
+        handler(context, content.to_string())?;
+        accounts.exit(&program_id)
+    }
+
+    #[test]
+    fn test_update_ad_content_keeps_trusted_ads_running() {
+        let mut fixture = create_mock_accounts(100);
+
+        update(&mut fixture, "New content").unwrap();
+
+        let ad: AdAccount = read_account(&fixture.ad.info());
+        assert_eq!(ad.content, "New content");
+        assert_eq!(ad.last_updated, TEST_TIMESTAMP);
+        assert!(ad.is_active);
+        assert!(!ad.pending_review);
+    }
+
+    #[test]
+    fn test_update_ad_content_sends_review_tier_ads_back_to_review() {
+        let review_reputation = ProtocolParams::default().review_reputation;
+        let mut fixture = create_mock_accounts(review_reputation - 1);
+
+        update(&mut fixture, "New content").unwrap();
+
+        let ad: AdAccount = read_account(&fixture.ad.info());
+        assert_eq!(ad.content, "New content");
+        assert!(!ad.is_active);
+        assert!(ad.pending_review);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::error::ErrorCode;
+use crate::events::AdvertiserUpdated;
+use crate::state::{AdvertiserAccount, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateAdvertiser<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Replaces the advertiser's name and email commitment. A new commitment
+/// has to be verified again.
+pub fn handler(
+    ctx: Context<UpdateAdvertiser>,
+    name: String,
+    email_commitment: [u8; 32],
+) -> Result<()> {
+    AdvertiserAccount::validate_profile(&name, &email_commitment)?;
+
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+
+    advertiser.name = name;
+    if advertiser.email_commitment != email_commitment {
+        advertiser.email_commitment = email_commitment;
+        advertiser.email_verified_at = 0;
+    }
+    advertiser.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(AdvertiserUpdated {
+        sequence: state.next_event_sequence()?,
+        advertiser: advertiser.key(),
+        authority: advertiser.authority,
+        name: advertiser.name.clone(),
+        email_commitment,
+        email_verified: advertiser.is_email_verified(),
+        timestamp: advertiser.last_updated,
+    });
+
+    msg!("Advertiser updated: {}", advertiser.key());
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use crate::error::ErrorCode;
+use crate::events::PaymentMintUpdated;
+use crate::state::{AcceptedMint, PaymentMintRegistry, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdatePaymentMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"payment_mint_registry"],
+        bump = payment_mint_registry.bump,
+    )]
+    pub payment_mint_registry: Account<'info, PaymentMintRegistry>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Replaces the amounts of an accepted mint, or stops and resumes deposits
+/// in it with `accepts_deposits`. A mint stays in the registry once added,
+/// so ads funded with it can always be settled and refunded.
+pub fn handler(ctx: Context<UpdatePaymentMint>, accepted: AcceptedMint) -> Result<()> {
+    accepted.validate()?;
+
+    let state = &mut ctx.accounts.state;
+    let registry = &mut ctx.accounts.payment_mint_registry;
+
+    *registry.get_mut(&accepted.mint)? = accepted;
+    registry.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(PaymentMintUpdated {
+        sequence: state.next_event_sequence()?,
+        accepted,
+        timestamp: registry.last_updated,
+    });
+
+    msg!("Payment mint updated: {}", accepted.mint);
+    Ok(())
+}
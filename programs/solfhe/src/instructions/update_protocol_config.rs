@@ -0,0 +1,49 @@
+use crate::error::ErrorCode;
+use crate::events::ProtocolConfigUpdated;
+use crate::state::{ProtocolConfig, ProtocolParams, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(mut, seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Replaces every parameter at once. Existing ads and match requests keep
+/// what they were created with; the new values apply to later instructions.
+pub fn handler(ctx: Context<UpdateProtocolConfig>, params: ProtocolParams) -> Result<()> {
+    let accounts = ctx.accounts;
+    write_params(&mut accounts.state, &mut accounts.protocol_config, params)?;
+
+    msg!("Protocol config updated");
+    Ok(())
+}
+
+/// Validates and stores `params`, shared with the single-purpose setters.
+pub(crate) fn write_params(
+    state: &mut StateAccount,
+    protocol_config: &mut ProtocolConfig,
+    params: ProtocolParams,
+) -> Result<()> {
+    params.validate()?;
+
+    protocol_config.params = params;
+    protocol_config.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(ProtocolConfigUpdated {
+        sequence: state.next_event_sequence()?,
+        params,
+        timestamp: protocol_config.last_updated,
+    });
+    Ok(())
+}
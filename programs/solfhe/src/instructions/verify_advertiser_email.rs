@@ -0,0 +1,47 @@
+use crate::error::ErrorCode;
+use crate::events::AdvertiserEmailVerified;
+use crate::state::{AdvertiserAccount, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct VerifyAdvertiserEmail<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(mut)]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Records that the protocol authority checked the advertiser's email
+/// off-chain against `email_commitment`. The commitment is passed back so an
+/// update landing in between is not verified by accident.
+pub fn handler(ctx: Context<VerifyAdvertiserEmail>, email_commitment: [u8; 32]) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let advertiser = &mut ctx.accounts.advertiser;
+
+    require!(
+        advertiser.email_commitment == email_commitment,
+        ErrorCode::EmailCommitmentMismatch
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    advertiser.email_verified_at = now;
+    advertiser.last_updated = now;
+
+    emit!(AdvertiserEmailVerified {
+        sequence: state.next_event_sequence()?,
+        advertiser: advertiser.key(),
+        email_commitment,
+        timestamp: now,
+    });
+
+    msg!("Advertiser email verified: {}", advertiser.key());
+    Ok(())
+}
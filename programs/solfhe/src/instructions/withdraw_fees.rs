@@ -0,0 +1,61 @@
+use crate::error::ErrorCode;
+use crate::events::FeesWithdrawn;
+use crate::state::StateAccount;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    /// The fee vault of any accepted payment mint
+    #[account(mut, seeds = [b"fee_vault", state.mint_seed(&fee_vault.mint)], bump)]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = destination.mint == fee_vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        amount <= ctx.accounts.fee_vault.amount,
+        ErrorCode::InsufficientFunds
+    );
+
+    // The fee vault is owned by the state PDA
+    let state_bump = [ctx.accounts.state.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"state", &state_bump]];
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.fee_vault.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+        authority: ctx.accounts.state.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)?;
+
+    let state = &mut ctx.accounts.state;
+    state.last_updated = Clock::get()?.unix_timestamp;
+
+    emit!(FeesWithdrawn {
+        sequence: state.next_event_sequence()?,
+        mint: ctx.accounts.fee_vault.mint,
+        recipient: ctx.accounts.destination.key(),
+        amount,
+        timestamp: state.last_updated,
+    });
+
+    msg!("Withdrew {} fee tokens", amount);
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use crate::error::ErrorCode;
+use crate::events::StakeWithdrawn;
+use crate::state::{AdvertiserAccount, AdvertiserStake, StateAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct WithdrawStake<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = !state.is_paused @ ErrorCode::ProtocolPaused,
+    )]
+    pub state: Account<'info, StateAccount>,
+
+    #[account(
+        seeds = [b"advertiser", authority.key().as_ref()],
+        bump = advertiser.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub advertiser: Account<'info, AdvertiserAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"advertiser_stake", advertiser.key().as_ref()],
+        bump = stake.bump,
+        has_one = advertiser,
+    )]
+    pub stake: Account<'info, AdvertiserStake>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Returns the unslashed stake, and the stake account's rent, to the
+/// advertiser once the cooldown after deactivation has passed.
+pub fn handler(ctx: Context<WithdrawStake>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let advertiser = &ctx.accounts.advertiser;
+    let stake = &ctx.accounts.stake;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(
+        !advertiser.is_active && stake.is_unlocked(now),
+        ErrorCode::StakeLocked
+    );
+
+    emit!(StakeWithdrawn {
+        sequence: state.next_event_sequence()?,
+        advertiser: advertiser.key(),
+        authority: advertiser.authority,
+        amount: stake.amount,
+        timestamp: now,
+    });
+
+    msg!("Withdrew {} lamports of stake", stake.amount);
+    Ok(())
+}
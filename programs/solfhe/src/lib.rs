@@ -1,13 +1,21 @@
 use anchor_lang::prelude::*;
 
+mod envelope;
 mod error;
 mod events;
 mod instructions;
+mod reputation;
 mod state;
+#[cfg(test)]
+mod test_utils;
+mod validation;
 
-pub use error::ErrorCode;
+pub use envelope::{CiphertextEnvelope, FheScheme, TRAIT_SCHEMA_V1};
+pub use error::{ErrorCategory, ErrorCode};
 use instructions::*;
-use state::*;
+pub use reputation::{
+    ReputationReason, ReputationReport, ReputationTier, INITIAL_REPUTATION, MAX_REPUTATION,
+};
 
 declare_id!("BxVYzMVCkq4Amxwz5sN8Z9EkATWSoTs99bkLUEmnEscm");
 
@@ -15,45 +23,293 @@ declare_id!("BxVYzMVCkq4Amxwz5sN8Z9EkATWSoTs99bkLUEmnEscm");
 pub mod solfhe {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        instructions::initialize::handler(ctx)
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        coprocessor: Pubkey,
+        fhe_keys: FheKeyParams,
+    ) -> Result<()> {
+        instructions::initialize::handler(ctx, coprocessor, fhe_keys)
+    }
+
+    pub fn set_authority(ctx: Context<SetAuthority>, new_authority: Pubkey) -> Result<()> {
+        instructions::set_authority::handler(ctx, new_authority)
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        instructions::accept_authority::handler(ctx)
+    }
+
+    pub fn set_payment_mint(ctx: Context<SetPaymentMint>) -> Result<()> {
+        instructions::set_payment_mint::handler(ctx)
+    }
+
+    pub fn add_payment_mint(ctx: Context<AddPaymentMint>, accepted: AcceptedMint) -> Result<()> {
+        instructions::add_payment_mint::handler(ctx, accepted)
+    }
+
+    pub fn update_payment_mint(
+        ctx: Context<UpdatePaymentMint>,
+        accepted: AcceptedMint,
+    ) -> Result<()> {
+        instructions::update_payment_mint::handler(ctx, accepted)
+    }
+
+    pub fn update_protocol_config(
+        ctx: Context<UpdateProtocolConfig>,
+        params: ProtocolParams,
+    ) -> Result<()> {
+        instructions::update_protocol_config::handler(ctx, params)
+    }
+
+    pub fn set_limits(ctx: Context<SetLimits>, limits: AdLimits) -> Result<()> {
+        instructions::set_limits::handler(ctx, limits)
+    }
+
+    pub fn set_match_threshold(
+        ctx: Context<SetMatchThreshold>,
+        match_threshold: u64,
+    ) -> Result<()> {
+        instructions::set_match_threshold::handler(ctx, match_threshold)
+    }
+
+    pub fn pause_protocol(ctx: Context<PauseProtocol>) -> Result<()> {
+        instructions::pause_protocol::handler(ctx)
+    }
+
+    pub fn resume_protocol(ctx: Context<ResumeProtocol>) -> Result<()> {
+        instructions::resume_protocol::handler(ctx)
+    }
+
+    pub fn initialize_rewards_vault(ctx: Context<InitializeRewardsVault>) -> Result<()> {
+        instructions::initialize_rewards_vault::handler(ctx)
+    }
+
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+        instructions::initialize_fee_vault::handler(ctx)
+    }
+
+    pub fn rotate_fhe_keys(ctx: Context<RotateFheKeys>, fhe_keys: FheKeyParams) -> Result<()> {
+        instructions::rotate_fhe_keys::handler(ctx, fhe_keys)
     }
 
     pub fn register_advertiser(
         ctx: Context<RegisterAdvertiser>,
         name: String,
-        email: String,
+        email_commitment: [u8; 32],
     ) -> Result<()> {
-        instructions::register_advertiser::handler(ctx, name, email)
+        instructions::register_advertiser::handler(ctx, name, email_commitment)
+    }
+
+    pub fn update_advertiser(
+        ctx: Context<UpdateAdvertiser>,
+        name: String,
+        email_commitment: [u8; 32],
+    ) -> Result<()> {
+        instructions::update_advertiser::handler(ctx, name, email_commitment)
+    }
+
+    pub fn verify_advertiser_email(
+        ctx: Context<VerifyAdvertiserEmail>,
+        email_commitment: [u8; 32],
+    ) -> Result<()> {
+        instructions::verify_advertiser_email::handler(ctx, email_commitment)
+    }
+
+    pub fn deactivate_advertiser(ctx: Context<DeactivateAdvertiser>) -> Result<()> {
+        instructions::deactivate_advertiser::handler(ctx)
     }
 
+    pub fn withdraw_stake(ctx: Context<WithdrawStake>) -> Result<()> {
+        instructions::withdraw_stake::handler(ctx)
+    }
+
+    pub fn close_advertiser(ctx: Context<CloseAdvertiser>) -> Result<()> {
+        instructions::close_advertiser::handler(ctx)
+    }
+
+    pub fn slash_advertiser(
+        ctx: Context<SlashAdvertiser>,
+        amount: u64,
+        reputation_penalty: u16,
+    ) -> Result<()> {
+        instructions::slash_advertiser::handler(ctx, amount, reputation_penalty)
+    }
+
+    pub fn report_advertiser(
+        ctx: Context<ReportAdvertiser>,
+        report: ReputationReport,
+    ) -> Result<()> {
+        instructions::report_advertiser::handler(ctx, report)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn create_ad(
         ctx: Context<CreateAd>,
         content: String,
-        encrypted_target_traits: Vec<u8>,
+        encrypted_target_traits: CiphertextEnvelope,
+        duration: i64,
+        budget: u64,
+        daily_cap: u64,
+        pacing: Pacing,
+        start_at: Option<i64>,
+    ) -> Result<()> {
+        instructions::create_ad::handler(
+            ctx,
+            content,
+            encrypted_target_traits,
+            duration,
+            budget,
+            daily_cap,
+            pacing,
+            start_at,
+        )
+    }
+
+    pub fn pause_ad(ctx: Context<PauseAd>) -> Result<()> {
+        instructions::pause_ad::handler(ctx)
+    }
+
+    pub fn approve_ad(ctx: Context<ApproveAd>) -> Result<()> {
+        instructions::approve_ad::handler(ctx)
+    }
+
+    pub fn resume_ad(ctx: Context<ResumeAd>) -> Result<()> {
+        instructions::resume_ad::handler(ctx)
+    }
+
+    pub fn update_ad_content(ctx: Context<UpdateAdContent>, content: String) -> Result<()> {
+        instructions::update_ad_content::handler(ctx, content)
+    }
+
+    pub fn extend_ad_duration(
+        ctx: Context<ExtendAdDuration>,
+        additional_duration: i64,
+    ) -> Result<()> {
+        instructions::extend_ad_duration::handler(ctx, additional_duration)
+    }
+
+    pub fn top_up_ad(ctx: Context<TopUpAd>, amount: u64) -> Result<()> {
+        instructions::top_up_ad::handler(ctx, amount)
+    }
+
+    pub fn expire_ad(ctx: Context<ExpireAd>) -> Result<()> {
+        instructions::expire_ad::handler(ctx)
+    }
+
+    pub fn close_ad(ctx: Context<CloseAd>) -> Result<()> {
+        instructions::close_ad::handler(ctx)
+    }
+
+    pub fn create_campaign(
+        ctx: Context<CreateCampaign>,
+        encrypted_target_traits: CiphertextEnvelope,
         duration: i64,
         budget: u64,
+        start_at: Option<i64>,
     ) -> Result<()> {
-        instructions::create_ad::handler(ctx, content, encrypted_target_traits, duration, budget)
+        instructions::create_campaign::handler(
+            ctx,
+            encrypted_target_traits,
+            duration,
+            budget,
+            start_at,
+        )
+    }
+
+    pub fn create_campaign_ad(
+        ctx: Context<CreateCampaignAd>,
+        content: String,
+        daily_cap: u64,
+        pacing: Pacing,
+    ) -> Result<()> {
+        instructions::create_campaign_ad::handler(ctx, content, daily_cap, pacing)
+    }
+
+    pub fn close_campaign(ctx: Context<CloseCampaign>) -> Result<()> {
+        instructions::close_campaign::handler(ctx)
+    }
+
+    pub fn migrate_ad_vault(ctx: Context<MigrateAdVault>) -> Result<()> {
+        instructions::migrate_ad_vault::handler(ctx)
+    }
+
+    pub fn register_publisher(ctx: Context<RegisterPublisher>, publisher: Pubkey) -> Result<()> {
+        instructions::register_publisher::handler(ctx, publisher)
+    }
+
+    pub fn revoke_publisher(ctx: Context<RevokePublisher>) -> Result<()> {
+        instructions::revoke_publisher::handler(ctx)
+    }
+
+    pub fn set_settlement_rates(
+        ctx: Context<SetSettlementRates>,
+        cpm_rate: u64,
+        cpc_rate: u64,
+        user_reward_bps: u16,
+    ) -> Result<()> {
+        instructions::set_settlement_rates::handler(ctx, cpm_rate, cpc_rate, user_reward_bps)
+    }
+
+    pub fn set_protocol_fee(ctx: Context<SetProtocolFee>, protocol_fee_bps: u16) -> Result<()> {
+        instructions::set_protocol_fee::handler(ctx, protocol_fee_bps)
+    }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        instructions::withdraw_fees::handler(ctx, amount)
+    }
+
+    pub fn record_impression(ctx: Context<Settle>, count: u32) -> Result<()> {
+        instructions::record_impression::handler(ctx, count)
+    }
+
+    pub fn record_click(ctx: Context<Settle>, count: u32) -> Result<()> {
+        instructions::record_click::handler(ctx, count)
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        instructions::claim_rewards::handler(ctx)
     }
 
     pub fn submit_user_profile(
         ctx: Context<SubmitUserProfile>,
-        encrypted_profile_data: Vec<u8>,
+        encrypted_profile_data: CiphertextEnvelope,
     ) -> Result<()> {
         instructions::submit_user_profile::handler(ctx, encrypted_profile_data)
     }
 
-    pub fn match_ads(ctx: Context<MatchAds>, encrypted_user_traits: Vec<u8>) -> Result<()> {
-        instructions::match_ads::handler(ctx, encrypted_user_traits)
+    pub fn request_match(ctx: Context<RequestMatch>, timeout: i64) -> Result<()> {
+        instructions::request_match::handler(ctx, timeout)
+    }
+
+    pub fn fulfil_match(
+        ctx: Context<FulfilMatch>,
+        scores_uri: String,
+        score_hashes: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::fulfil_match::handler(ctx, scores_uri, score_hashes)
     }
-}
 
-// Constants
-pub const MIN_AD_BUDGET: u64 = 1_000_000; // 0.001 SOL
-pub const MAX_AD_DURATION: i64 = 30 * 24 * 60 * 60; // 30 days in seconds
-pub const FHE_MATCH_THRESHOLD: u64 = 75;
+    pub fn cancel_match(ctx: Context<CancelMatch>) -> Result<()> {
+        instructions::cancel_match::handler(ctx)
+    }
+}
 
 // Re-export important structs for external use
-pub use events::{AdCreated, AdsMatched, AdvertiserRegistered, UserProfileSubmitted};
-pub use state::{AdAccount, AdvertiserAccount, MatchedAdsAccount, StateAccount, UserProfile};
+pub use events::{
+    AdApproved, AdBudgetToppedUp, AdClosed, AdContentUpdated, AdCreated, AdDailyCapReached,
+    AdDurationExtended, AdExpired, AdPaused, AdResumed, AdVaultMigrated, AdsMatched,
+    AdvertiserClosed, AdvertiserDeactivated, AdvertiserEmailVerified, AdvertiserRegistered,
+    AdvertiserSlashed, AdvertiserUpdated, AuthorityProposed, AuthorityTransferred, BudgetRefunded,
+    CampaignClosed, CampaignCreated, CampaignPaused, ClickSettled, FeeVaultInitialized,
+    FeesWithdrawn, FheKeysRotated, ImpressionSettled, MatchCancelled, MatchRequested,
+    PaymentMintAccepted, PaymentMintSet, PaymentMintUpdated, ProgramInitialized,
+    ProtocolConfigUpdated, ProtocolFeeCollected, ProtocolFeeUpdated, ProtocolPaused,
+    ProtocolResumed, PublisherRegistered, PublisherRevoked, ReputationUpdated, RewardsClaimed,
+    RewardsVaultInitialized, SettlementRatesUpdated, StakeWithdrawn, UserProfileSubmitted,
+};
+pub use state::{
+    AcceptedMint, AdAccount, AdLimits, AdvertiserAccount, AdvertiserStake, Campaign,
+    EncryptedMatch, FheKeyParams, FheKeyRegistry, MatchRequest, MatchedAdsAccount, Pacing,
+    PaymentMintRegistry, ProtocolConfig, ProtocolParams, PublisherAccount, StateAccount,
+    UserProfile, UserRewards, MAX_CIPHERTEXT_URI_LENGTH, MAX_MATCHED_ADS,
+};
@@ -0,0 +1,304 @@
+use crate::error::ErrorCode;
+use crate::state::{AdvertiserAccount, ProtocolParams, MAX_BPS};
+use anchor_lang::prelude::*;
+
+/// Score of a newly registered advertiser, and the score every other score
+/// decays towards.
+pub const INITIAL_REPUTATION: u16 = 100;
+pub const MAX_REPUTATION: u16 = 1000;
+
+/// Share of a closed ad's budget that, when refunded unspent, counts against
+/// the advertiser.
+pub const HIGH_REFUND_BPS: u16 = 5_000;
+/// Most points a single closed ad can earn from settled spend.
+pub const MAX_CAMPAIGN_BONUS: u16 = 10;
+
+// Distances to `INITIAL_REPUTATION` stay below 2^10, so this many half-lives
+// decay any score all the way
+const MAX_DECAY_HALVINGS: i64 = 15;
+
+/// Policy violation reported by the protocol authority.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReputationReport {
+    /// An ad's content broke the content policy.
+    FlaggedContent,
+    /// Fraud such as self-clicking or a stolen payment source.
+    Fraud,
+}
+
+/// Why an advertiser's score changed, as reported in `ReputationUpdated`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReputationReason {
+    /// A standalone ad or a campaign of the advertiser was closed; see
+    /// `campaign_outcome`.
+    CampaignClosed,
+    FlaggedContent,
+    Fraud,
+}
+
+impl From<ReputationReport> for ReputationReason {
+    fn from(report: ReputationReport) -> Self {
+        match report {
+            ReputationReport::FlaggedContent => ReputationReason::FlaggedContent,
+            ReputationReport::Fraud => ReputationReason::Fraud,
+        }
+    }
+}
+
+/// What `create_ad` allows an advertiser with a given score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReputationTier {
+    /// Below `review_reputation`: new ads and content updates wait for
+    /// `approve_ad`, and budgets are capped.
+    Review,
+    /// Below `trusted_reputation`: budgets are capped at
+    /// `limited_budget_cap`.
+    Limited,
+    Trusted,
+}
+
+impl ProtocolParams {
+    pub fn reputation_tier(&self, score: u16) -> ReputationTier {
+        if score < self.review_reputation {
+            ReputationTier::Review
+        } else if score < self.trusted_reputation {
+            ReputationTier::Limited
+        } else {
+            ReputationTier::Trusted
+        }
+    }
+
+    /// Checks what an advertiser with `score` has deposited into one ad or
+    /// campaign against `limited_budget_cap`. `deposited` is gross, before
+    /// the protocol fee, like the budget passed to `create_ad`.
+    pub fn check_budget_cap(&self, score: u16, deposited: u64) -> Result<()> {
+        require!(
+            self.reputation_tier(score) == ReputationTier::Trusted
+                || deposited <= self.limited_budget_cap,
+            ErrorCode::BudgetExceedsReputationCap
+        );
+        Ok(())
+    }
+
+    /// Points lost for a reported violation.
+    pub fn report_penalty(&self, report: ReputationReport) -> u16 {
+        match report {
+            ReputationReport::FlaggedContent => self.flagged_content_penalty,
+            ReputationReport::Fraud => self.fraud_report_penalty,
+        }
+    }
+
+    /// Score change for closing a standalone ad or a campaign that spent
+    /// `spent_budget` of `budget` with `refund` unspent: one point per
+    /// `reputation_spend_unit` settled, up to `MAX_CAMPAIGN_BONUS`, less
+    /// `high_refund_penalty` when more than `HIGH_REFUND_BPS` of the budget
+    /// comes back.
+    pub fn campaign_outcome(&self, budget: u64, spent_budget: u64, refund: u64) -> i32 {
+        let bonus = (spent_budget / self.reputation_spend_unit).min(MAX_CAMPAIGN_BONUS as u64);
+        let refund_bps = if budget == 0 {
+            0
+        } else {
+            refund.min(budget) as u128 * MAX_BPS as u128 / budget as u128
+        };
+        let penalty = if refund_bps > HIGH_REFUND_BPS as u128 {
+            self.high_refund_penalty
+        } else {
+            0
+        };
+        bonus as i32 - penalty as i32
+    }
+}
+
+impl AdvertiserAccount {
+    /// Moves the score towards `INITIAL_REPUTATION`, halving the distance
+    /// once per `half_life` seconds since `reputation_updated_at`, so both
+    /// penalties and bonuses fade. Only whole half-lives are applied; the
+    /// remainder carries over to the next refresh.
+    pub fn refresh_reputation(&mut self, now: i64, half_life: i64) {
+        let elapsed = now.saturating_sub(self.reputation_updated_at);
+        if half_life <= 0 || elapsed < half_life {
+            return;
+        }
+        let halvings = elapsed / half_life;
+        self.reputation_updated_at = if halvings >= MAX_DECAY_HALVINGS {
+            now
+        } else {
+            self.reputation_updated_at + halvings * half_life
+        };
+
+        // A deactivated advertiser keeps the score it was deactivated with
+        if !self.is_active {
+            return;
+        }
+        let shift = halvings.min(MAX_DECAY_HALVINGS) as u32;
+        self.reputation_score = if self.reputation_score > INITIAL_REPUTATION {
+            INITIAL_REPUTATION + ((self.reputation_score - INITIAL_REPUTATION) >> shift)
+        } else {
+            INITIAL_REPUTATION - ((INITIAL_REPUTATION - self.reputation_score) >> shift)
+        };
+    }
+
+    /// Applies the score change of a closed campaign, capped at
+    /// `MAX_REPUTATION`. Campaign outcomes alone never deactivate an
+    /// advertiser, so a penalty stops at a score of 1.
+    pub fn apply_campaign_outcome(&mut self, delta: i32) {
+        if delta < 0 {
+            let floor = self.reputation_score.min(1);
+            self.reputation_score = self
+                .reputation_score
+                .saturating_sub(delta.unsigned_abs().min(u16::MAX as u32) as u16)
+                .max(floor);
+        } else {
+            self.reputation_score = self
+                .reputation_score
+                .saturating_add(delta.min(u16::MAX as i32) as u16)
+                .min(MAX_REPUTATION);
+        }
+    }
+
+    /// Lowers the reputation score after a policy violation. An advertiser
+    /// whose score reaches zero is deactivated.
+    pub fn penalize(&mut self, points: u16) {
+        self.reputation_score = self.reputation_score.saturating_sub(points);
+        if self.reputation_score == 0 {
+            self.is_active = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AdAccount;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn advertiser(score: u16) -> AdvertiserAccount {
+        AdvertiserAccount {
+            reputation_score: score,
+            is_active: true,
+            ..AdvertiserAccount::default()
+        }
+    }
+
+    #[test]
+    fn test_penalize_deactivates_at_zero() {
+        let mut advertiser = advertiser(30);
+        advertiser.penalize(20);
+        assert_eq!(advertiser.reputation_score, 10);
+        assert!(advertiser.is_active);
+
+        advertiser.penalize(20);
+        assert_eq!(advertiser.reputation_score, 0);
+        assert!(!advertiser.is_active);
+    }
+
+    #[test]
+    fn test_apply_campaign_outcome_is_clamped() {
+        let mut advertiser = advertiser(MAX_REPUTATION - 5);
+        advertiser.apply_campaign_outcome(10);
+        assert_eq!(advertiser.reputation_score, MAX_REPUTATION);
+
+        advertiser.apply_campaign_outcome(-(MAX_REPUTATION as i32) - 1);
+        assert_eq!(advertiser.reputation_score, 1);
+        assert!(advertiser.is_active);
+    }
+
+    #[test]
+    fn test_refresh_reputation_decays_towards_initial() {
+        let mut low = advertiser(20);
+        low.refresh_reputation(2 * DAY + 1, DAY);
+        // Distance 80 halved twice
+        assert_eq!(low.reputation_score, 80);
+        assert_eq!(low.reputation_updated_at, 2 * DAY);
+
+        let mut high = advertiser(300);
+        high.refresh_reputation(DAY, DAY);
+        assert_eq!(high.reputation_score, 200);
+
+        high.refresh_reputation(100 * DAY, DAY);
+        assert_eq!(high.reputation_score, INITIAL_REPUTATION);
+        assert_eq!(high.reputation_updated_at, 100 * DAY);
+    }
+
+    #[test]
+    fn test_refresh_reputation_carries_partial_half_lives() {
+        let mut advertiser = advertiser(20);
+        for now in (DAY / 2..=2 * DAY).step_by((DAY / 2) as usize) {
+            advertiser.refresh_reputation(now, DAY);
+        }
+        assert_eq!(advertiser.reputation_score, 80);
+    }
+
+    #[test]
+    fn test_refresh_reputation_keeps_deactivated_score() {
+        let mut advertiser = advertiser(0);
+        advertiser.is_active = false;
+        advertiser.refresh_reputation(10 * DAY, DAY);
+        assert_eq!(advertiser.reputation_score, 0);
+        assert_eq!(advertiser.reputation_updated_at, 10 * DAY);
+    }
+
+    #[test]
+    fn test_reputation_tier() {
+        let params = ProtocolParams::default();
+        assert_eq!(
+            params.reputation_tier(params.review_reputation - 1),
+            ReputationTier::Review
+        );
+        assert_eq!(
+            params.reputation_tier(params.review_reputation),
+            ReputationTier::Limited
+        );
+        assert_eq!(
+            params.reputation_tier(INITIAL_REPUTATION),
+            ReputationTier::Trusted
+        );
+    }
+
+    #[test]
+    fn test_budget_cap_counts_gross_deposits() {
+        let params = ProtocolParams {
+            limited_budget_cap: 10_000,
+            ..ProtocolParams::default()
+        };
+        let limited = params.review_reputation;
+        assert!(params.check_budget_cap(limited, 10_000).is_ok());
+        assert_eq!(
+            params.check_budget_cap(limited, 10_001).unwrap_err(),
+            ErrorCode::BudgetExceedsReputationCap.into()
+        );
+        assert!(params
+            .check_budget_cap(params.trusted_reputation, 10_001)
+            .is_ok());
+
+        // After 9_000 deposited at a 2.5% fee the ad's budget is 8_775, yet
+        // only 1_000 more fits under the cap
+        let ad = AdAccount {
+            budget: 8_775,
+            deposited: 9_000,
+            ..AdAccount::default()
+        };
+        assert!(params
+            .check_budget_cap(limited, ad.deposited + 1_000)
+            .is_ok());
+        assert!(params
+            .check_budget_cap(limited, ad.deposited + 1_001)
+            .is_err());
+    }
+
+    #[test]
+    fn test_campaign_outcome() {
+        let params = ProtocolParams::default();
+        let unit = params.reputation_spend_unit;
+        assert_eq!(params.campaign_outcome(10 * unit, 7 * unit, 3 * unit), 7);
+        assert_eq!(
+            params.campaign_outcome(10 * unit, 2 * unit, 8 * unit),
+            2 - params.high_refund_penalty as i32
+        );
+        assert_eq!(
+            params.campaign_outcome(100 * unit, 100 * unit, 0),
+            MAX_CAMPAIGN_BONUS as i32
+        );
+    }
+}
@@ -0,0 +1,1723 @@
+use crate::envelope::CiphertextEnvelope;
+use crate::error::ErrorCode;
+use crate::reputation::MAX_REPUTATION;
+use crate::validation;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+// Size bounds for variable-length account fields
+pub const MAX_CONTENT_LENGTH: usize = 1000;
+pub const MAX_MATCHED_ADS: usize = 8;
+pub const MAX_CIPHERTEXT_URI_LENGTH: usize = 200;
+// Ads, campaigns and profiles store a serialized `CiphertextEnvelope`; the
+// ciphertexts themselves are off-chain
+pub const MAX_ENCRYPTED_TRAITS_SIZE: usize =
+    CiphertextEnvelope::HEADER_SIZE + MAX_CIPHERTEXT_URI_LENGTH;
+pub const MAX_PROFILE_DATA_SIZE: usize =
+    CiphertextEnvelope::HEADER_SIZE + MAX_CIPHERTEXT_URI_LENGTH;
+pub const MAX_KEY_URI_LENGTH: usize = 200;
+pub const MAX_NAME_LENGTH: usize = 50;
+pub const MAX_PAYMENT_MINTS: usize = 16;
+
+/// Denominator of basis-point shares.
+pub const MAX_BPS: u16 = 10_000;
+
+/// Zeroed bytes at the end of every account. Fields added after deployment
+/// are appended just before them and grow the account's `SPACE`, so the
+/// offsets of existing fields never change.
+pub const RESERVED_SPACE: usize = 32;
+
+pub const MAX_MATCH_CANDIDATES: usize = MAX_MATCHED_ADS;
+
+/// Length of the days daily caps apply to. Days start at multiples of this
+/// on the cluster clock, i.e. at midnight UTC.
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+const EMAIL_COMMITMENT_DOMAIN: &[u8] = b"solfhe:advertiser_email:v1";
+
+/// Global protocol state, stored at `[b"state"]`.
+#[account]
+#[derive(Default)]
+pub struct StateAccount {
+    pub authority: Pubkey,
+    /// Off-chain FHE coprocessor allowed to post encrypted match results.
+    pub coprocessor: Pubkey,
+    /// Primary payment mint, priced by `cpm_rate`, `cpc_rate` and
+    /// `ProtocolConfig`. Further mints are accepted through the
+    /// `PaymentMintRegistry`.
+    pub payment_mint: Pubkey,
+    pub advertiser_count: u64,
+    pub user_count: u64,
+    pub ad_count: u64,
+    /// Summed over all payment mints, in their own base units.
+    pub total_budget: u64,
+    pub last_updated: i64,
+    pub bump: u8,
+    /// Sequence number of the last emitted event.
+    pub event_sequence: u64,
+    /// Charged per 1000 impressions, in base units of `payment_mint`.
+    pub cpm_rate: u64,
+    /// Charged per click, in base units of `payment_mint`.
+    pub cpc_rate: u64,
+    /// Share of every settlement accrued to the user who saw the ad.
+    pub user_reward_bps: u16,
+    /// Share of every ad deposit and settlement paid into the fee vault.
+    pub protocol_fee_bps: u16,
+    /// Summed over all payment mints, in their own base units.
+    pub total_fees_collected: u64,
+    /// Proposed by `set_authority`; becomes `authority` once it signs
+    /// `accept_authority`. `Pubkey::default()` when no handover is pending.
+    pub pending_authority: Pubkey,
+    /// While set, only the authority's own instructions are accepted.
+    pub is_paused: bool,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl StateAccount {
+    pub const SPACE: usize = 32 // authority
+        + 32 // coprocessor
+        + 32 // payment_mint
+        + 8 // advertiser_count
+        + 8 // user_count
+        + 8 // ad_count
+        + 8 // total_budget
+        + 8 // last_updated
+        + 1 // bump
+        + 8 // event_sequence
+        + 8 // cpm_rate
+        + 8 // cpc_rate
+        + 2 // user_reward_bps
+        + 2 // protocol_fee_bps
+        + 8 // total_fees_collected
+        + 32 // pending_authority
+        + 1 // is_paused
+        + RESERVED_SPACE; // reserved
+
+    /// Claims the sequence number for the next emitted event.
+    pub fn next_event_sequence(&mut self) -> Result<u64> {
+        self.event_sequence = self
+            .event_sequence
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(self.event_sequence)
+    }
+
+    /// Cost of `count` impressions at the current CPM rate, rounded down.
+    pub fn impression_cost(&self, count: u32) -> Result<u64> {
+        cpm_cost(self.cpm_rate, count)
+    }
+
+    pub fn click_cost(&self, count: u32) -> Result<u64> {
+        cpc_cost(self.cpc_rate, count)
+    }
+
+    /// Seed that keys the fee vault, rewards vault and user rewards of `mint`.
+    /// Those of the primary `payment_mint` predate additional mints and are
+    /// derived without it; an empty seed leaves an address unchanged.
+    pub fn mint_seed<'a>(&self, mint: &'a Pubkey) -> &'a [u8] {
+        if *mint == self.payment_mint {
+            &[]
+        } else {
+            mint.as_ref()
+        }
+    }
+
+    /// Registry entry of an additional payment mint; `None` for the primary
+    /// `payment_mint`, which needs no registry.
+    pub fn accepted_mint<'a>(
+        &self,
+        mint: &Pubkey,
+        registry: Option<&'a PaymentMintRegistry>,
+    ) -> Result<Option<&'a AcceptedMint>> {
+        if *mint == self.payment_mint {
+            return Ok(None);
+        }
+        let registry = registry.ok_or(ErrorCode::PaymentMintNotAccepted)?;
+        registry.get(mint).map(Some)
+    }
+
+    /// `params` for amounts paid in `mint`: unchanged for the primary
+    /// `payment_mint`, or with the amounts set for an additional mint.
+    pub fn mint_params(
+        &self,
+        mint: &Pubkey,
+        params: &ProtocolParams,
+        registry: Option<&PaymentMintRegistry>,
+    ) -> Result<ProtocolParams> {
+        Ok(self
+            .accepted_mint(mint, registry)?
+            .map_or(*params, |accepted| accepted.apply(params)))
+    }
+
+    /// Like `mint_params`, for a new deposit, which a mint that no longer
+    /// accepts deposits rejects.
+    pub fn deposit_params(
+        &self,
+        mint: &Pubkey,
+        params: &ProtocolParams,
+        registry: Option<&PaymentMintRegistry>,
+    ) -> Result<ProtocolParams> {
+        if let Some(accepted) = self.accepted_mint(mint, registry)? {
+            require!(accepted.accepts_deposits, ErrorCode::PaymentMintNotAccepted);
+        }
+        self.mint_params(mint, params, registry)
+    }
+
+    /// CPM and CPC rates of ads paid in `mint`.
+    pub fn settlement_rates(
+        &self,
+        mint: &Pubkey,
+        registry: Option<&PaymentMintRegistry>,
+    ) -> Result<(u64, u64)> {
+        Ok(self
+            .accepted_mint(mint, registry)?
+            .map_or((self.cpm_rate, self.cpc_rate), |accepted| {
+                (accepted.cpm_rate, accepted.cpc_rate)
+            }))
+    }
+
+    /// Takes the protocol fee out of `amount`, returning the fee.
+    pub fn collect_fee(&mut self, amount: u64) -> Result<u64> {
+        let fee = bps_of(amount, self.protocol_fee_bps);
+        self.total_fees_collected = self
+            .total_fees_collected
+            .checked_add(fee)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(fee)
+    }
+}
+
+/// Tunable protocol parameters, stored at `[b"protocol_config"]`. Created
+/// with the defaults by `initialize` and changed by the authority with
+/// `update_protocol_config`.
+#[account]
+#[derive(Default)]
+pub struct ProtocolConfig {
+    pub last_updated: i64,
+    pub bump: u8,
+    /// Last before `reserved`, so parameters appended to `ProtocolParams`
+    /// grow the account at its end.
+    pub params: ProtocolParams,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl ProtocolConfig {
+    pub const SPACE: usize = 8 // last_updated
+        + 1 // bump
+        + ProtocolParams::SPACE // params
+        + RESERVED_SPACE; // reserved
+}
+
+/// The ad bounds of `ProtocolParams`, set on their own with `set_limits`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdLimits {
+    /// Capped by `MAX_CONTENT_LENGTH`, which sizes `AdAccount`.
+    pub max_content_length: u32,
+    pub min_duration: i64,
+    pub max_duration: i64,
+    pub min_budget: u64,
+}
+
+impl AdLimits {
+    /// Copies the limits into the matching fields of `params`.
+    pub fn apply_to(self, params: &mut ProtocolParams) {
+        params.max_content_length = self.max_content_length;
+        params.min_ad_duration = self.min_duration;
+        params.max_ad_duration = self.max_duration;
+        params.min_ad_budget = self.min_budget;
+    }
+}
+
+/// Parameters held by `ProtocolConfig`. Bounds that size accounts, such as
+/// `MAX_CONTENT_LENGTH`, stay constants and cap the matching parameter here.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtocolParams {
+    pub max_content_length: u32,
+    /// Bounds on an ad's total run time in seconds, measured from `start_at`.
+    /// `max_ad_duration` also bounds how far ahead an ad can be scheduled.
+    pub min_ad_duration: i64,
+    pub max_ad_duration: i64,
+    /// In base units of `payment_mint`, before the protocol fee.
+    pub min_ad_budget: u64,
+    /// Lamports locked in an `AdvertiserStake` at registration.
+    pub advertiser_stake: u64,
+    /// Bounds on how long a match request waits for the coprocessor.
+    pub min_match_timeout: i64,
+    pub max_match_timeout: i64,
+    /// Minimum decrypted score for an ad to count as matched. Scores stay
+    /// encrypted on-chain, so clients apply this after decrypting.
+    pub match_threshold: u64,
+    /// Seconds after deactivation before the stake can be withdrawn.
+    pub stake_cooldown: i64,
+    /// Seconds for the distance of a reputation score to
+    /// `INITIAL_REPUTATION` to halve.
+    pub reputation_half_life: i64,
+    /// Below this score new ads need `approve_ad` before they run.
+    pub review_reputation: u16,
+    /// Below this score ad budgets are capped at `limited_budget_cap`.
+    pub trusted_reputation: u16,
+    pub limited_budget_cap: u64,
+    /// Settled spend worth one reputation point when an ad is closed.
+    pub reputation_spend_unit: u64,
+    /// Points lost per `ReputationReport`, and for closing an ad with most
+    /// of its budget unspent.
+    pub flagged_content_penalty: u16,
+    pub fraud_report_penalty: u16,
+    pub high_refund_penalty: u16,
+}
+
+impl Default for ProtocolParams {
+    fn default() -> Self {
+        Self {
+            max_content_length: MAX_CONTENT_LENGTH as u32,
+            min_ad_duration: 60 * 60,           // 1 hour
+            max_ad_duration: 30 * 24 * 60 * 60, // 30 days
+            min_ad_budget: 100_000_000,
+            advertiser_stake: 10_000_000,    // 0.01 SOL
+            min_match_timeout: 60,           // 1 minute
+            max_match_timeout: 24 * 60 * 60, // 1 day
+            match_threshold: 75,
+            stake_cooldown: 7 * 24 * 60 * 60,        // 7 days
+            reputation_half_life: 30 * 24 * 60 * 60, // 30 days
+            review_reputation: 40,
+            trusted_reputation: 80,
+            limited_budget_cap: 10_000_000_000,
+            reputation_spend_unit: 1_000_000_000,
+            flagged_content_penalty: 20,
+            fraud_report_penalty: 50,
+            high_refund_penalty: 5,
+        }
+    }
+}
+
+impl ProtocolParams {
+    pub const SPACE: usize = 4 // max_content_length
+        + 8 // min_ad_duration
+        + 8 // max_ad_duration
+        + 8 // min_ad_budget
+        + 8 // advertiser_stake
+        + 8 // min_match_timeout
+        + 8 // max_match_timeout
+        + 8 // match_threshold
+        + 8 // stake_cooldown
+        + 8 // reputation_half_life
+        + 2 // review_reputation
+        + 2 // trusted_reputation
+        + 8 // limited_budget_cap
+        + 8 // reputation_spend_unit
+        + 2 // flagged_content_penalty
+        + 2 // fraud_report_penalty
+        + 2; // high_refund_penalty
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.max_content_length > 0 && self.max_content_length as usize <= MAX_CONTENT_LENGTH,
+            ErrorCode::InvalidProtocolParams
+        );
+        require!(
+            self.min_ad_duration > 0 && self.min_ad_duration <= self.max_ad_duration,
+            ErrorCode::InvalidProtocolParams
+        );
+        require!(self.min_ad_budget > 0, ErrorCode::InvalidProtocolParams);
+        require!(self.stake_cooldown >= 0, ErrorCode::InvalidProtocolParams);
+        require!(
+            self.min_match_timeout > 0 && self.min_match_timeout <= self.max_match_timeout,
+            ErrorCode::InvalidProtocolParams
+        );
+        require!(
+            self.reputation_half_life > 0,
+            ErrorCode::InvalidProtocolParams
+        );
+        require!(
+            self.review_reputation <= self.trusted_reputation
+                && self.trusted_reputation <= MAX_REPUTATION,
+            ErrorCode::InvalidProtocolParams
+        );
+        require!(
+            self.limited_budget_cap >= self.min_ad_budget && self.reputation_spend_unit > 0,
+            ErrorCode::InvalidProtocolParams
+        );
+        Ok(())
+    }
+
+    pub fn check_content(&self, content: &str) -> Result<()> {
+        require!(
+            !content.is_empty() && content.len() <= self.max_content_length as usize,
+            ErrorCode::InvalidAdContent
+        );
+        Ok(())
+    }
+
+    pub fn check_duration(&self, duration: i64) -> Result<()> {
+        require!(
+            duration >= self.min_ad_duration && duration <= self.max_ad_duration,
+            ErrorCode::InvalidAdDuration
+        );
+        Ok(())
+    }
+
+    pub fn check_match_timeout(&self, timeout: i64) -> Result<()> {
+        require!(
+            (self.min_match_timeout..=self.max_match_timeout).contains(&timeout),
+            ErrorCode::InvalidMatchTimeout
+        );
+        Ok(())
+    }
+}
+
+/// SPL mints accepted for ad budgets besides the primary
+/// `StateAccount::payment_mint`, stored at `[b"payment_mint_registry"]`.
+/// Each mint has its own fee vault at `[b"fee_vault", mint]` and rewards vault
+/// at `[b"rewards_vault", mint]`.
+#[account]
+#[derive(Default)]
+pub struct PaymentMintRegistry {
+    pub mints: Vec<AcceptedMint>,
+    pub last_updated: i64,
+    pub bump: u8,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl PaymentMintRegistry {
+    pub const SPACE: usize = 4 + MAX_PAYMENT_MINTS * AcceptedMint::SPACE // mints
+        + 8 // last_updated
+        + 1 // bump
+        + RESERVED_SPACE; // reserved
+
+    pub fn get(&self, mint: &Pubkey) -> Result<&AcceptedMint> {
+        self.mints
+            .iter()
+            .find(|accepted| accepted.mint == *mint)
+            .ok_or_else(|| error!(ErrorCode::PaymentMintNotAccepted))
+    }
+
+    pub fn get_mut(&mut self, mint: &Pubkey) -> Result<&mut AcceptedMint> {
+        self.mints
+            .iter_mut()
+            .find(|accepted| accepted.mint == *mint)
+            .ok_or_else(|| error!(ErrorCode::PaymentMintNotAccepted))
+    }
+
+    pub fn add(&mut self, accepted: AcceptedMint) -> Result<()> {
+        require!(
+            self.get(&accepted.mint).is_err(),
+            ErrorCode::PaymentMintAlreadyAccepted
+        );
+        require!(
+            self.mints.len() < MAX_PAYMENT_MINTS,
+            ErrorCode::PaymentMintRegistryFull
+        );
+        self.mints.push(accepted);
+        Ok(())
+    }
+}
+
+/// An additional payment mint and the amounts that `ProtocolParams` and
+/// `StateAccount` otherwise give in base units of the primary mint.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AcceptedMint {
+    pub mint: Pubkey,
+    pub min_ad_budget: u64,
+    pub limited_budget_cap: u64,
+    pub reputation_spend_unit: u64,
+    pub cpm_rate: u64,
+    pub cpc_rate: u64,
+    /// Cleared to stop new ads and top-ups in the mint. Ads already funded
+    /// with it keep settling and are refunded in it.
+    pub accepts_deposits: bool,
+}
+
+impl AcceptedMint {
+    pub const SPACE: usize = 32 // mint
+        + 8 // min_ad_budget
+        + 8 // limited_budget_cap
+        + 8 // reputation_spend_unit
+        + 8 // cpm_rate
+        + 8 // cpc_rate
+        + 1; // accepts_deposits
+
+    /// Same bounds as the matching `ProtocolParams`.
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.min_ad_budget > 0
+                && self.limited_budget_cap >= self.min_ad_budget
+                && self.reputation_spend_unit > 0,
+            ErrorCode::InvalidProtocolParams
+        );
+        Ok(())
+    }
+
+    /// `params` with the amounts denominated in this mint.
+    pub fn apply(&self, params: &ProtocolParams) -> ProtocolParams {
+        ProtocolParams {
+            min_ad_budget: self.min_ad_budget,
+            limited_budget_cap: self.limited_budget_cap,
+            reputation_spend_unit: self.reputation_spend_unit,
+            ..*params
+        }
+    }
+}
+
+/// Network FHE keys that every ciphertext must be encrypted under, stored at
+/// `[b"fhe_key_registry"]`. The keys themselves live off-chain at `key_uri`;
+/// clients and the coprocessor check them against the hashes recorded here.
+#[account]
+#[derive(Default)]
+pub struct FheKeyRegistry {
+    /// SHA-256 of the serialized network public key.
+    pub public_key_hash: [u8; 32],
+    /// SHA-256 of the serialized server key used by the coprocessor.
+    pub server_key_hash: [u8; 32],
+    pub key_uri: String,
+    /// Identifier of the TFHE parameter set the keys were generated with.
+    pub parameter_set: u16,
+    /// Incremented on every rotation; ciphertexts record the epoch they target.
+    pub epoch: u64,
+    pub rotated_at: i64,
+    pub bump: u8,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl FheKeyRegistry {
+    pub const SPACE: usize = 32 // public_key_hash
+        + 32 // server_key_hash
+        + 4 + MAX_KEY_URI_LENGTH // key_uri
+        + 2 // parameter_set
+        + 8 // epoch
+        + 8 // rotated_at
+        + 1 // bump
+        + RESERVED_SPACE; // reserved
+
+    /// Replaces the registered keys, starting a new epoch when `rotate` is set.
+    pub fn set_keys(&mut self, params: FheKeyParams, now: i64, rotate: bool) -> Result<()> {
+        params.validate()?;
+
+        if rotate {
+            self.epoch = self.epoch.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        }
+        self.public_key_hash = params.public_key_hash;
+        self.server_key_hash = params.server_key_hash;
+        self.key_uri = params.key_uri;
+        self.parameter_set = params.parameter_set;
+        self.rotated_at = now;
+        Ok(())
+    }
+}
+
+/// Key material registered in `initialize` and `rotate_fhe_keys`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct FheKeyParams {
+    pub public_key_hash: [u8; 32],
+    pub server_key_hash: [u8; 32],
+    pub key_uri: String,
+    pub parameter_set: u16,
+}
+
+impl FheKeyParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.public_key_hash != [0; 32] && self.server_key_hash != [0; 32],
+            ErrorCode::InvalidFheKeys
+        );
+        require!(
+            !self.key_uri.is_empty() && self.key_uri.len() <= MAX_KEY_URI_LENGTH,
+            ErrorCode::InvalidFheKeys
+        );
+        Ok(())
+    }
+}
+
+/// A registered advertiser, stored at `[b"advertiser", authority]`.
+///
+/// The contact email never goes on-chain: only a salted commitment to it is
+/// stored, see `AdvertiserAccount::email_commitment`.
+#[account]
+#[derive(Default)]
+pub struct AdvertiserAccount {
+    pub authority: Pubkey,
+    pub name: String,
+    pub email_commitment: [u8; 32],
+    /// Number of ads created, also the index of the next ad's PDA.
+    pub ad_count: u64,
+    pub total_budget: u64,
+    pub reputation_score: u16,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub last_updated: i64,
+    pub bump: u8,
+    /// Ads created and not yet closed.
+    pub open_ad_count: u64,
+    /// When the protocol authority checked the email behind
+    /// `email_commitment`; 0 while unverified.
+    pub email_verified_at: i64,
+    /// Start of the reputation decay period not yet applied to
+    /// `reputation_score`.
+    pub reputation_updated_at: i64,
+    /// Number of campaigns created, also the index of the next campaign's PDA.
+    pub campaign_count: u64,
+    /// Campaigns created and not yet closed.
+    pub open_campaign_count: u64,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl AdvertiserAccount {
+    pub const SPACE: usize = 32 // authority
+        + 4 + MAX_NAME_LENGTH // name
+        + 32 // email_commitment
+        + 8 // ad_count
+        + 8 // total_budget
+        + 2 // reputation_score
+        + 1 // is_active
+        + 8 // created_at
+        + 8 // last_updated
+        + 1 // bump
+        + 8 // open_ad_count
+        + 8 // email_verified_at
+        + 8 // reputation_updated_at
+        + 8 // campaign_count
+        + 8 // open_campaign_count
+        + RESERVED_SPACE; // reserved
+
+    /// Checks a name and email commitment before they are stored.
+    pub fn validate_profile(name: &str, email_commitment: &[u8; 32]) -> Result<()> {
+        validation::validate_name(name)?;
+        require!(
+            *email_commitment != [0; 32],
+            ErrorCode::InvalidAdvertiserEmail
+        );
+        Ok(())
+    }
+
+    /// Commitment an advertiser registers instead of their email: a
+    /// domain-separated hash of the authority, a random 32-byte salt and the
+    /// normalized email. The advertiser hands the email and salt to the
+    /// protocol authority off-chain, which recomputes the commitment and
+    /// attests it with `verify_advertiser_email`.
+    pub fn email_commitment(authority: &Pubkey, salt: &[u8; 32], email: &str) -> Result<[u8; 32]> {
+        let email = validation::normalize_email(email)?;
+        Ok(hashv(&[
+            EMAIL_COMMITMENT_DOMAIN,
+            authority.as_ref(),
+            salt,
+            email.as_bytes(),
+        ])
+        .to_bytes())
+    }
+
+    /// Whether the authority verified the email behind the current commitment.
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified_at != 0
+    }
+}
+
+/// Lamports an advertiser locked at registration, stored at
+/// `[b"advertiser_stake", advertiser]`. The authority can slash it for policy
+/// violations; the rest is returned by `withdraw_stake` once the advertiser
+/// is deactivated and the cooldown has passed.
+#[account]
+#[derive(Default)]
+pub struct AdvertiserStake {
+    pub advertiser: Pubkey,
+    /// Staked lamports, on top of the account's rent.
+    pub amount: u64,
+    pub total_slashed: u64,
+    /// Zero while the advertiser is active.
+    pub unlocks_at: i64,
+    pub created_at: i64,
+    pub last_updated: i64,
+    pub bump: u8,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl AdvertiserStake {
+    pub const SPACE: usize = 32 // advertiser
+        + 8 // amount
+        + 8 // total_slashed
+        + 8 // unlocks_at
+        + 8 // created_at
+        + 8 // last_updated
+        + 1 // bump
+        + RESERVED_SPACE; // reserved
+
+    pub fn is_unlocked(&self, now: i64) -> bool {
+        self.unlocks_at != 0 && now >= self.unlocks_at
+    }
+}
+
+/// A publisher or attestor allowed to report impressions and clicks, stored
+/// at `[b"publisher", authority]`. Registered and revoked by the protocol
+/// authority.
+#[account]
+#[derive(Default)]
+pub struct PublisherAccount {
+    pub authority: Pubkey,
+    pub impressions: u64,
+    pub clicks: u64,
+    /// Total paid out to the publisher across all ads.
+    pub earned: u64,
+    pub created_at: i64,
+    pub last_updated: i64,
+    pub bump: u8,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl PublisherAccount {
+    pub const SPACE: usize = 32 // authority
+        + 8 // impressions
+        + 8 // clicks
+        + 8 // earned
+        + 8 // created_at
+        + 8 // last_updated
+        + 1 // bump
+        + RESERVED_SPACE; // reserved
+}
+
+/// How an ad spreads its spend over the days it runs.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pacing {
+    /// Spend as fast as events are settled, up to the daily cap. First, so
+    /// ads from before pacing read as accelerated once resized.
+    #[default]
+    Accelerated,
+    /// Each day may spend an equal share of what was unspent when it began,
+    /// spread over the days left until the ad ends.
+    Even,
+}
+
+/// An ad and its FHE-encrypted targeting traits, stored at
+/// `[b"ad", advertiser, ad_index]`. Its budget is escrowed in a token account
+/// at `[b"ad_vault", ad]` owned by the state PDA, unless the ad belongs to a
+/// `Campaign` and spends from the campaign's vault.
+#[account]
+#[derive(Default)]
+pub struct AdAccount {
+    pub advertiser: Pubkey,
+    pub content: String,
+    /// Serialized `CiphertextEnvelope`, evaluated only by the coprocessor.
+    pub encrypted_target_traits: Vec<u8>,
+    /// `FheKeyRegistry::epoch` the traits were encrypted under.
+    pub key_epoch: u64,
+    pub trait_schema: u16,
+    /// Run time in seconds, measured from `start_at`.
+    pub duration: i64,
+    pub budget: u64,
+    pub spent_budget: u64,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub last_updated: i64,
+    /// False for ads funded into the legacy shared treasury that have not
+    /// been moved to their own vault by `migrate_ad_vault` yet.
+    pub has_vault: bool,
+    pub vault_bump: u8,
+    /// Created by a low-reputation advertiser and not yet approved; the ad
+    /// cannot run until `approve_ad`.
+    pub pending_review: bool,
+    /// `Campaign` the ad belongs to; `Pubkey::default()` for a standalone ad.
+    pub campaign: Pubkey,
+    /// Most the ad may spend per day; 0 for no cap.
+    pub daily_cap: u64,
+    pub pacing: Pacing,
+    /// Day, counted in `SECONDS_PER_DAY` since the Unix epoch, that
+    /// `day_spent` was spent on.
+    pub spend_day: i64,
+    pub day_spent: u64,
+    /// When the ad starts running; `created_at` unless scheduled later.
+    pub start_at: i64,
+    /// Unspent budget returned by `expire_ad` while the ad stays open.
+    pub refunded: u64,
+    /// Mint the budget was paid in; settlements and refunds are paid in it.
+    pub mint: Pubkey,
+    /// Paid in by the advertiser, including the protocol fee, which the
+    /// reputation cap applies to. 0 for a campaign ad.
+    pub deposited: u64,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl AdAccount {
+    pub const SPACE: usize = 32 // advertiser
+        + 4 + MAX_CONTENT_LENGTH // content
+        + 4 + MAX_ENCRYPTED_TRAITS_SIZE // encrypted_target_traits
+        + 8 // key_epoch
+        + 2 // trait_schema
+        + 8 // duration
+        + 8 // budget
+        + 8 // spent_budget
+        + 8 // impressions
+        + 8 // clicks
+        + 1 // is_active
+        + 8 // created_at
+        + 8 // last_updated
+        + 1 // has_vault
+        + 1 // vault_bump
+        + 1 // pending_review
+        + 32 // campaign
+        + 8 // daily_cap
+        + 1 // pacing
+        + 8 // spend_day
+        + 8 // day_spent
+        + 8 // start_at
+        + 8 // refunded
+        + 32 // mint
+        + 8 // deposited
+        + RESERVED_SPACE; // reserved
+
+    /// Budget deposited but neither spent nor refunded.
+    pub fn remaining_budget(&self) -> Result<u64> {
+        self.budget
+            .checked_sub(self.spent_budget)
+            .and_then(|left| left.checked_sub(self.refunded))
+            .ok_or_else(|| error!(ErrorCode::Overflow))
+    }
+
+    pub fn ends_at(&self) -> Result<i64> {
+        self.start_at
+            .checked_add(self.duration)
+            .ok_or_else(|| error!(ErrorCode::Overflow))
+    }
+
+    /// Whether the ad can be matched and settled at `now`: active, and
+    /// within `[start_at, ends_at)`.
+    pub fn is_running(&self, now: i64) -> Result<bool> {
+        Ok(self.is_active && self.start_at <= now && now < self.ends_at()?)
+    }
+
+    /// Charges up to `cost` against the remaining budget and returns the
+    /// amount actually charged. The ad is paused once its budget is exhausted.
+    pub fn charge(&mut self, cost: u64) -> Result<u64> {
+        let charged = cost.min(self.remaining_budget()?);
+        self.spent_budget = self
+            .spent_budget
+            .checked_add(charged)
+            .ok_or(ErrorCode::Overflow)?;
+        if self.remaining_budget()? == 0 {
+            self.is_active = false;
+        }
+        Ok(charged)
+    }
+
+    /// Checks the `daily_cap` of an ad paid at `(cpm_rate, cpc_rate)` out of
+    /// `budget`: 0 for no cap, or enough for the cheaper of one impression
+    /// and one click and no more than the budget.
+    pub fn validate_daily_cap(daily_cap: u64, budget: u64, rates: (u64, u64)) -> Result<()> {
+        if daily_cap == 0 {
+            return Ok(());
+        }
+        let (cpm_rate, cpc_rate) = rates;
+        let min_cost = cpm_cost(cpm_rate, 1)?.min(cpc_cost(cpc_rate, 1)?);
+        require!(
+            daily_cap >= min_cost && daily_cap <= budget,
+            ErrorCode::InvalidDailyCap
+        );
+        Ok(())
+    }
+
+    /// Most the ad may spend on the day of `now`, in total. `unspent` is the
+    /// budget still to be paced: the ad's own, or its campaign's.
+    pub fn daily_limit(&self, unspent: u64, now: i64) -> Result<u64> {
+        let cap = match self.daily_cap {
+            0 => u64::MAX,
+            cap => cap,
+        };
+        if self.pacing == Pacing::Accelerated {
+            return Ok(cap);
+        }
+
+        // The day's share is taken from what was unspent when it began,
+        // counting the day the ad ends on as a whole day
+        let unspent = unspent
+            .checked_add(self.spent_on_day_of(now))
+            .ok_or(ErrorCode::Overflow)?;
+        let day_start = now.div_euclid(SECONDS_PER_DAY) * SECONDS_PER_DAY;
+        let days_left = self
+            .ends_at()?
+            .checked_sub(day_start)
+            .and_then(|left| left.checked_add(SECONDS_PER_DAY - 1))
+            .ok_or(ErrorCode::Overflow)?
+            .div_euclid(SECONDS_PER_DAY)
+            .max(1) as u64;
+        let share = unspent / days_left + u64::from(unspent % days_left != 0);
+        Ok(cap.min(share))
+    }
+
+    /// Spend still allowed on the day of `now` under `daily_limit`.
+    pub fn daily_allowance(&self, unspent: u64, now: i64) -> Result<u64> {
+        Ok(self
+            .daily_limit(unspent, now)?
+            .saturating_sub(self.spent_on_day_of(now)))
+    }
+
+    fn spent_on_day_of(&self, now: i64) -> u64 {
+        if now.div_euclid(SECONDS_PER_DAY) == self.spend_day {
+            self.day_spent
+        } else {
+            0
+        }
+    }
+
+    /// Adds `amount` to the spend of the day of `now`, starting a new day's
+    /// count when the last spend was on an earlier day.
+    pub fn record_daily_spend(&mut self, amount: u64, now: i64) -> Result<()> {
+        let day = now.div_euclid(SECONDS_PER_DAY);
+        if day != self.spend_day {
+            self.spend_day = day;
+            self.day_spent = 0;
+        }
+        self.day_spent = self
+            .day_spent
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Start of the day after the one `day_spent` was spent on.
+    pub fn next_spend_day_at(&self) -> i64 {
+        self.spend_day
+            .saturating_add(1)
+            .saturating_mul(SECONDS_PER_DAY)
+    }
+
+    pub fn is_campaign_ad(&self) -> bool {
+        self.campaign != Pubkey::default()
+    }
+
+    /// Records `amount` charged to the ad's campaign. A campaign ad's budget
+    /// is what it has drawn from the campaign, so none of it is left over.
+    pub fn draw(&mut self, amount: u64) -> Result<()> {
+        self.budget = self.budget.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        self.spent_budget = self
+            .spent_budget
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Token account the ad is paid from: its own `[b"ad_vault", ad]`, or
+    /// `[b"campaign_vault", campaign]` for a campaign ad. `vault_bump` is the
+    /// bump of whichever applies.
+    pub fn budget_vault(&self, ad: &Pubkey) -> Result<Pubkey> {
+        let bump = [self.vault_bump];
+        let seeds: [&[u8]; 3] = if self.is_campaign_ad() {
+            [b"campaign_vault", self.campaign.as_ref(), &bump]
+        } else {
+            [b"ad_vault", ad.as_ref(), &bump]
+        };
+        Pubkey::create_program_address(&seeds, &crate::ID)
+            .map_err(|_| error!(ErrorCode::InvalidAdVault))
+    }
+}
+
+/// A group of ads sharing one budget, schedule and FHE-encrypted targeting,
+/// stored at `[b"campaign", advertiser, campaign_index]`. The budget is
+/// escrowed in a token account at `[b"campaign_vault", campaign]` owned by the
+/// state PDA, and every settlement of a member ad is charged to it.
+#[account]
+#[derive(Default)]
+pub struct Campaign {
+    pub advertiser: Pubkey,
+    /// Serialized `CiphertextEnvelope`, copied into every member ad.
+    pub encrypted_target_traits: Vec<u8>,
+    /// `FheKeyRegistry::epoch` the traits were encrypted under.
+    pub key_epoch: u64,
+    pub trait_schema: u16,
+    /// Run time in seconds, measured from `start_at`.
+    pub duration: i64,
+    pub budget: u64,
+    /// Totals over all member ads, closed ones included.
+    pub spent_budget: u64,
+    pub impressions: u64,
+    pub clicks: u64,
+    /// Number of member ads created.
+    pub ad_count: u64,
+    /// Member ads created and not yet closed.
+    pub open_ad_count: u64,
+    /// Cleared once the budget is exhausted.
+    pub is_active: bool,
+    pub created_at: i64,
+    pub last_updated: i64,
+    pub bump: u8,
+    pub vault_bump: u8,
+    /// Mint the budget was paid in, and that of every member ad.
+    pub mint: Pubkey,
+    /// When member ads start running; the campaign ends `duration` seconds
+    /// later.
+    pub start_at: i64,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl Campaign {
+    pub const SPACE: usize = 32 // advertiser
+        + 4 + MAX_ENCRYPTED_TRAITS_SIZE // encrypted_target_traits
+        + 8 // key_epoch
+        + 2 // trait_schema
+        + 8 // duration
+        + 8 // budget
+        + 8 // spent_budget
+        + 8 // impressions
+        + 8 // clicks
+        + 8 // ad_count
+        + 8 // open_ad_count
+        + 1 // is_active
+        + 8 // created_at
+        + 8 // last_updated
+        + 1 // bump
+        + 1 // vault_bump
+        + 32 // mint
+        + 8 // start_at
+        + RESERVED_SPACE; // reserved
+
+    /// Budget deposited but not yet spent by any member ad.
+    pub fn remaining_budget(&self) -> Result<u64> {
+        self.budget
+            .checked_sub(self.spent_budget)
+            .ok_or_else(|| error!(ErrorCode::Overflow))
+    }
+
+    pub fn ends_at(&self) -> Result<i64> {
+        self.start_at
+            .checked_add(self.duration)
+            .ok_or_else(|| error!(ErrorCode::Overflow))
+    }
+
+    /// Charges up to `cost` against the shared budget and returns the amount
+    /// actually charged. The campaign is paused once its budget is exhausted.
+    pub fn charge(&mut self, cost: u64) -> Result<u64> {
+        let charged = cost.min(self.remaining_budget()?);
+        self.spent_budget = self
+            .spent_budget
+            .checked_add(charged)
+            .ok_or(ErrorCode::Overflow)?;
+        if self.remaining_budget()? == 0 {
+            self.is_active = false;
+        }
+        Ok(charged)
+    }
+}
+
+/// A user's FHE-encrypted profile, stored at `[b"user_profile", user]`.
+#[account]
+#[derive(Default)]
+pub struct UserProfile {
+    pub user: Pubkey,
+    /// Serialized `CiphertextEnvelope`, evaluated only by the coprocessor.
+    pub encrypted_data: Vec<u8>,
+    /// `FheKeyRegistry::epoch` the profile was encrypted under.
+    pub key_epoch: u64,
+    pub trait_schema: u16,
+    pub created_at: i64,
+    pub last_updated: i64,
+    pub bump: u8,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl UserProfile {
+    pub const SPACE: usize = 32 // user
+        + 4 + MAX_PROFILE_DATA_SIZE // encrypted_data
+        + 8 // key_epoch
+        + 2 // trait_schema
+        + 8 // created_at
+        + 8 // last_updated
+        + 1 // bump
+        + RESERVED_SPACE; // reserved
+}
+
+/// A user's pending request for the coprocessor to score a set of candidate
+/// ads, stored at `[b"match_request", user]`. Closed when fulfilled or
+/// cancelled; an expired request may be replaced by a new one.
+#[account]
+#[derive(Default)]
+pub struct MatchRequest {
+    pub user: Pubkey,
+    pub user_profile: Pubkey,
+    pub candidate_ads: Vec<Pubkey>,
+    /// `FheKeyRegistry::epoch` the profile and candidates were encrypted under.
+    pub key_epoch: u64,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl MatchRequest {
+    pub const SPACE: usize = 32 // user
+        + 32 // user_profile
+        + 4 + MAX_MATCH_CANDIDATES * 32 // candidate_ads
+        + 8 // key_epoch
+        + 8 // created_at
+        + 8 // expires_at
+        + 1 // bump
+        + RESERVED_SPACE; // reserved
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.expires_at
+    }
+}
+
+/// Encrypted score of a single ad against a user's profile. Only the user's
+/// client key can decrypt it.
+///
+/// A score ciphertext is several kilobytes, more than fits in a transaction,
+/// so only its hash is stored; the ciphertext itself is published off-chain
+/// at `MatchedAdsAccount::scores_uri`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct EncryptedMatch {
+    pub ad: Pubkey,
+    /// SHA-256 of the bincode-serialized score ciphertext.
+    pub score_hash: [u8; 32],
+    pub key_epoch: u64,
+    pub matched_at: i64,
+}
+
+impl EncryptedMatch {
+    pub const SPACE: usize = 32 // ad
+        + 32 // score_hash
+        + 8 // key_epoch
+        + 8; // matched_at
+}
+
+/// Scores of the user's latest fulfilled match request, stored at
+/// `[b"matched_ads", user]`.
+#[account]
+#[derive(Default)]
+pub struct MatchedAdsAccount {
+    pub user: Pubkey,
+    pub matches: Vec<EncryptedMatch>,
+    pub last_updated: i64,
+    pub bump: u8,
+    /// Location of the bincode `Vec<Vec<u8>>` of serialized score
+    /// ciphertexts, in the order of `matches`.
+    pub scores_uri: String,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl MatchedAdsAccount {
+    pub const SPACE: usize = 32 // user
+        + 4 + MAX_MATCHED_ADS * EncryptedMatch::SPACE // matches
+        + 8 // last_updated
+        + 1 // bump
+        + 4 + MAX_CIPHERTEXT_URI_LENGTH // scores_uri
+        + RESERVED_SPACE; // reserved
+
+    pub fn contains(&self, ad: &Pubkey) -> bool {
+        self.matches.iter().any(|matched| matched.ad == *ad)
+    }
+}
+
+/// Rewards a user has earned for ads they were matched with and viewed,
+/// stored at `[b"user_rewards", user, mint_seed]` per payment mint (see
+/// `StateAccount::mint_seed`). Accrued tokens are held in the mint's rewards
+/// vault until claimed.
+#[account]
+#[derive(Default)]
+pub struct UserRewards {
+    pub user: Pubkey,
+    /// Earned but not yet claimed.
+    pub accrued: u64,
+    pub total_earned: u64,
+    pub total_claimed: u64,
+    pub created_at: i64,
+    pub last_claimed_at: i64,
+    pub last_updated: i64,
+    pub bump: u8,
+    pub mint: Pubkey,
+    pub reserved: [u8; RESERVED_SPACE],
+}
+
+impl UserRewards {
+    pub const SPACE: usize = 32 // user
+        + 8 // accrued
+        + 8 // total_earned
+        + 8 // total_claimed
+        + 8 // created_at
+        + 8 // last_claimed_at
+        + 8 // last_updated
+        + 1 // bump
+        + 32 // mint
+        + RESERVED_SPACE; // reserved
+
+    pub fn accrue(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.accrued = self
+            .accrued
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        self.total_earned = self
+            .total_earned
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        self.last_updated = now;
+        Ok(())
+    }
+}
+
+/// Cost of `count` impressions at `cpm_rate` per 1000, rounded down.
+pub fn cpm_cost(cpm_rate: u64, count: u32) -> Result<u64> {
+    let cost = u128::from(cpm_rate) * u128::from(count) / 1000;
+    u64::try_from(cost).map_err(|_| error!(ErrorCode::Overflow))
+}
+
+pub fn cpc_cost(cpc_rate: u64, count: u32) -> Result<u64> {
+    cpc_rate
+        .checked_mul(u64::from(count))
+        .ok_or_else(|| error!(ErrorCode::Overflow))
+}
+
+/// `bps` basis points of `amount`, rounded down. `bps` must not exceed `MAX_BPS`.
+pub fn bps_of(amount: u64, bps: u16) -> u64 {
+    (u128::from(amount) * u128::from(bps) / u128::from(MAX_BPS)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialized_len<T: AnchorSerialize>(account: &T) -> usize {
+        account.try_to_vec().unwrap().len()
+    }
+
+    #[test]
+    fn test_state_account_space() {
+        assert_eq!(
+            serialized_len(&StateAccount::default()),
+            StateAccount::SPACE
+        );
+    }
+
+    #[test]
+    fn test_protocol_config_space() {
+        assert_eq!(
+            serialized_len(&ProtocolConfig::default()),
+            ProtocolConfig::SPACE
+        );
+    }
+
+    #[test]
+    fn test_protocol_params() {
+        let params = ProtocolParams::default();
+        assert!(params.validate().is_ok());
+        assert!(params.check_content("ad").is_ok());
+        assert!(params.check_content("").is_err());
+        assert!(params.check_duration(params.min_ad_duration).is_ok());
+        assert!(params.check_duration(params.max_ad_duration + 1).is_err());
+        assert!(params.check_match_timeout(params.max_match_timeout).is_ok());
+        assert!(params.check_match_timeout(0).is_err());
+
+        let too_long = ProtocolParams {
+            max_content_length: MAX_CONTENT_LENGTH as u32 + 1,
+            ..params
+        };
+        assert_eq!(
+            too_long.validate().unwrap_err(),
+            ErrorCode::InvalidProtocolParams.into()
+        );
+        let inverted = ProtocolParams {
+            min_match_timeout: params.max_match_timeout + 1,
+            ..params
+        };
+        assert_eq!(
+            inverted.validate().unwrap_err(),
+            ErrorCode::InvalidProtocolParams.into()
+        );
+    }
+
+    #[test]
+    fn test_payment_mint_registry_space() {
+        let registry = PaymentMintRegistry {
+            mints: vec![AcceptedMint::default(); MAX_PAYMENT_MINTS],
+            ..PaymentMintRegistry::default()
+        };
+        assert_eq!(serialized_len(&registry), PaymentMintRegistry::SPACE);
+    }
+
+    #[test]
+    fn test_payment_mint_registry() {
+        let usdc = AcceptedMint {
+            mint: Pubkey::new_unique(),
+            min_ad_budget: 1_000_000,
+            limited_budget_cap: 100_000_000,
+            reputation_spend_unit: 10_000_000,
+            cpm_rate: 2_000,
+            cpc_rate: 50,
+            accepts_deposits: true,
+        };
+        assert!(usdc.validate().is_ok());
+
+        let mut registry = PaymentMintRegistry::default();
+        registry.add(usdc).unwrap();
+        assert_eq!(
+            registry.add(usdc).unwrap_err(),
+            ErrorCode::PaymentMintAlreadyAccepted.into()
+        );
+        for _ in 1..MAX_PAYMENT_MINTS {
+            registry
+                .add(AcceptedMint {
+                    mint: Pubkey::new_unique(),
+                    ..usdc
+                })
+                .unwrap();
+        }
+        assert_eq!(
+            registry
+                .add(AcceptedMint {
+                    mint: Pubkey::new_unique(),
+                    ..usdc
+                })
+                .unwrap_err(),
+            ErrorCode::PaymentMintRegistryFull.into()
+        );
+
+        let state = StateAccount {
+            payment_mint: Pubkey::new_unique(),
+            cpm_rate: 2_500,
+            cpc_rate: 40,
+            ..StateAccount::default()
+        };
+        let params = ProtocolParams::default();
+
+        // The primary mint needs no registry
+        assert_eq!(
+            state
+                .mint_params(&state.payment_mint, &params, None)
+                .unwrap(),
+            params
+        );
+        assert_eq!(
+            state.settlement_rates(&state.payment_mint, None).unwrap(),
+            (2_500, 40)
+        );
+
+        let usdc_params = state
+            .deposit_params(&usdc.mint, &params, Some(&registry))
+            .unwrap();
+        assert_eq!(usdc_params.min_ad_budget, usdc.min_ad_budget);
+        assert_eq!(usdc_params.max_ad_duration, params.max_ad_duration);
+        assert_eq!(
+            state.settlement_rates(&usdc.mint, Some(&registry)).unwrap(),
+            (2_000, 50)
+        );
+        assert_eq!(
+            state.mint_params(&usdc.mint, &params, None).unwrap_err(),
+            ErrorCode::PaymentMintNotAccepted.into()
+        );
+
+        // A delisted mint takes no new deposits but still settles
+        registry.get_mut(&usdc.mint).unwrap().accepts_deposits = false;
+        assert_eq!(
+            state
+                .deposit_params(&usdc.mint, &params, Some(&registry))
+                .unwrap_err(),
+            ErrorCode::PaymentMintNotAccepted.into()
+        );
+        assert!(state.settlement_rates(&usdc.mint, Some(&registry)).is_ok());
+    }
+
+    #[test]
+    fn test_primary_mint_keeps_unkeyed_addresses() {
+        let state = StateAccount {
+            payment_mint: Pubkey::new_unique(),
+            ..StateAccount::default()
+        };
+        let (unkeyed, _) = Pubkey::find_program_address(&[b"fee_vault"], &crate::ID);
+        let (primary, _) = Pubkey::find_program_address(
+            &[b"fee_vault", state.mint_seed(&state.payment_mint)],
+            &crate::ID,
+        );
+        assert_eq!(primary, unkeyed);
+
+        let other = Pubkey::new_unique();
+        assert_eq!(state.mint_seed(&other), other.as_ref());
+    }
+
+    #[test]
+    fn test_fhe_key_registry_space() {
+        let registry = FheKeyRegistry {
+            key_uri: "k".repeat(MAX_KEY_URI_LENGTH),
+            ..FheKeyRegistry::default()
+        };
+        assert_eq!(serialized_len(&registry), FheKeyRegistry::SPACE);
+    }
+
+    #[test]
+    fn test_advertiser_account_space() {
+        let advertiser = AdvertiserAccount {
+            name: "n".repeat(MAX_NAME_LENGTH),
+            ..AdvertiserAccount::default()
+        };
+        assert_eq!(serialized_len(&advertiser), AdvertiserAccount::SPACE);
+    }
+
+    #[test]
+    fn test_email_commitment_binds_authority_and_salt() {
+        let authority = Pubkey::new_unique();
+        let salt = [7; 32];
+        let commit = |authority: &Pubkey, salt: &[u8; 32], email: &str| {
+            AdvertiserAccount::email_commitment(authority, salt, email).unwrap()
+        };
+        let commitment = commit(&authority, &salt, "ads@example.com");
+
+        assert_eq!(commitment, commit(&authority, &salt, "ads@EXAMPLE.com"));
+        assert_ne!(commitment, commit(&authority, &[8; 32], "ads@example.com"));
+        assert_ne!(
+            commitment,
+            commit(&Pubkey::new_unique(), &salt, "ads@example.com")
+        );
+        assert!(AdvertiserAccount::email_commitment(&authority, &salt, "ads").is_err());
+        assert!(AdvertiserAccount::validate_profile("Advertiser", &commitment).is_ok());
+        assert!(AdvertiserAccount::validate_profile("Advertiser", &[0; 32]).is_err());
+    }
+
+    #[test]
+    fn test_advertiser_stake_space() {
+        assert_eq!(
+            serialized_len(&AdvertiserStake::default()),
+            AdvertiserStake::SPACE
+        );
+    }
+
+    #[test]
+    fn test_stake_unlocks_after_cooldown() {
+        let mut stake = AdvertiserStake::default();
+        assert!(!stake.is_unlocked(1_000));
+
+        stake.unlocks_at = 500;
+        assert!(!stake.is_unlocked(499));
+        assert!(stake.is_unlocked(500));
+    }
+
+    #[test]
+    fn test_ad_account_space() {
+        let ad = AdAccount {
+            content: "c".repeat(MAX_CONTENT_LENGTH),
+            encrypted_target_traits: vec![0; MAX_ENCRYPTED_TRAITS_SIZE],
+            ..AdAccount::default()
+        };
+        assert_eq!(serialized_len(&ad), AdAccount::SPACE);
+    }
+
+    #[test]
+    fn test_ad_budget_and_schedule() {
+        let mut ad = AdAccount {
+            budget: 500,
+            spent_budget: 120,
+            is_active: true,
+            created_at: 500,
+            start_at: 1_000,
+            duration: 3_600,
+            ..AdAccount::default()
+        };
+        assert_eq!(ad.remaining_budget().unwrap(), 380);
+        assert_eq!(ad.ends_at().unwrap(), 4_600);
+        assert!(!ad.is_running(999).unwrap());
+        assert!(ad.is_running(1_000).unwrap());
+        assert!(!ad.is_running(4_600).unwrap());
+
+        ad.refunded = 380;
+        assert_eq!(ad.remaining_budget().unwrap(), 0);
+
+        let overspent = AdAccount {
+            budget: 100,
+            spent_budget: 101,
+            ..AdAccount::default()
+        };
+        assert_eq!(
+            overspent.remaining_budget().unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+    }
+
+    #[test]
+    fn test_campaign_space() {
+        let campaign = Campaign {
+            encrypted_target_traits: vec![0; MAX_ENCRYPTED_TRAITS_SIZE],
+            ..Campaign::default()
+        };
+        assert_eq!(serialized_len(&campaign), Campaign::SPACE);
+    }
+
+    #[test]
+    fn test_campaign_ends_after_duration_from_start() {
+        let campaign = Campaign {
+            created_at: 1_000,
+            start_at: 5_000,
+            duration: 600,
+            ..Campaign::default()
+        };
+        assert_eq!(campaign.ends_at().unwrap(), 5_600);
+    }
+
+    #[test]
+    fn test_campaign_ad_draws_from_campaign() {
+        let mut campaign = Campaign {
+            budget: 100,
+            spent_budget: 70,
+            is_active: true,
+            ..Campaign::default()
+        };
+        let mut ad = AdAccount {
+            campaign: Pubkey::new_unique(),
+            is_active: true,
+            ..AdAccount::default()
+        };
+
+        ad.draw(campaign.charge(20).unwrap()).unwrap();
+        ad.draw(campaign.charge(20).unwrap()).unwrap();
+        assert_eq!(campaign.spent_budget, 100);
+        assert!(!campaign.is_active);
+        assert_eq!(ad.spent_budget, 30);
+        assert_eq!(ad.remaining_budget().unwrap(), 0);
+        assert!(ad.is_active);
+    }
+
+    #[test]
+    fn test_budget_vault() {
+        let ad_key = Pubkey::new_unique();
+        let (ad_vault, ad_vault_bump) =
+            Pubkey::find_program_address(&[b"ad_vault", ad_key.as_ref()], &crate::ID);
+        let ad = AdAccount {
+            vault_bump: ad_vault_bump,
+            ..AdAccount::default()
+        };
+        assert_eq!(ad.budget_vault(&ad_key).unwrap(), ad_vault);
+
+        let campaign = Pubkey::new_unique();
+        let (campaign_vault, campaign_vault_bump) =
+            Pubkey::find_program_address(&[b"campaign_vault", campaign.as_ref()], &crate::ID);
+        let campaign_ad = AdAccount {
+            campaign,
+            vault_bump: campaign_vault_bump,
+            ..AdAccount::default()
+        };
+        assert_eq!(campaign_ad.budget_vault(&ad_key).unwrap(), campaign_vault);
+
+        // About half of all bumps put the address on the curve
+        let off_curve = |bump: u8| {
+            Pubkey::create_program_address(&[b"ad_vault", ad_key.as_ref(), &[bump]], &crate::ID)
+                .is_ok()
+        };
+        let bad_bump = (0..=u8::MAX).find(|&bump| !off_curve(bump)).unwrap();
+        let broken = AdAccount {
+            vault_bump: bad_bump,
+            ..AdAccount::default()
+        };
+        assert_eq!(
+            broken.budget_vault(&ad_key).unwrap_err(),
+            ErrorCode::InvalidAdVault.into()
+        );
+    }
+
+    #[test]
+    fn test_publisher_account_space() {
+        assert_eq!(
+            serialized_len(&PublisherAccount::default()),
+            PublisherAccount::SPACE
+        );
+    }
+
+    #[test]
+    fn test_settlement_costs() {
+        let state = StateAccount {
+            cpm_rate: 2_500,
+            cpc_rate: 40,
+            ..StateAccount::default()
+        };
+        assert_eq!(state.impression_cost(1).unwrap(), 2);
+        assert_eq!(state.impression_cost(400).unwrap(), 1_000);
+        assert_eq!(state.click_cost(3).unwrap(), 120);
+
+        let expensive = StateAccount {
+            cpc_rate: u64::MAX,
+            ..StateAccount::default()
+        };
+        assert_eq!(
+            expensive.click_cost(2).unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+    }
+
+    #[test]
+    fn test_collect_fee() {
+        let mut state = StateAccount {
+            protocol_fee_bps: 250,
+            ..StateAccount::default()
+        };
+        assert_eq!(state.collect_fee(10_000).unwrap(), 250);
+        assert_eq!(state.collect_fee(39).unwrap(), 0);
+        assert_eq!(state.total_fees_collected, 250);
+    }
+
+    #[test]
+    fn test_charge_pauses_exhausted_ad() {
+        let mut ad = AdAccount {
+            budget: 100,
+            spent_budget: 70,
+            is_active: true,
+            ..AdAccount::default()
+        };
+        assert_eq!(ad.charge(20).unwrap(), 20);
+        assert!(ad.is_active);
+
+        // Only the remaining budget is charged
+        assert_eq!(ad.charge(20).unwrap(), 10);
+        assert_eq!(ad.spent_budget, 100);
+        assert!(!ad.is_active);
+    }
+
+    #[test]
+    fn test_validate_daily_cap() {
+        // One impression costs 2 and one click 40
+        let rates = (2_500, 40);
+        assert!(AdAccount::validate_daily_cap(0, 1_000, rates).is_ok());
+        assert!(AdAccount::validate_daily_cap(2, 1_000, rates).is_ok());
+        assert!(AdAccount::validate_daily_cap(1_000, 1_000, rates).is_ok());
+
+        assert_eq!(
+            AdAccount::validate_daily_cap(1, 1_000, rates).unwrap_err(),
+            ErrorCode::InvalidDailyCap.into()
+        );
+        assert_eq!(
+            AdAccount::validate_daily_cap(1_001, 1_000, rates).unwrap_err(),
+            ErrorCode::InvalidDailyCap.into()
+        );
+    }
+
+    #[test]
+    fn test_daily_cap_resets_each_day() {
+        let mut ad = AdAccount {
+            daily_cap: 100,
+            pacing: Pacing::Accelerated,
+            duration: 10 * SECONDS_PER_DAY,
+            ..AdAccount::default()
+        };
+        let noon = SECONDS_PER_DAY + SECONDS_PER_DAY / 2;
+        assert_eq!(ad.daily_allowance(1_000, noon).unwrap(), 100);
+
+        ad.record_daily_spend(70, noon).unwrap();
+        assert_eq!(ad.daily_allowance(930, noon).unwrap(), 30);
+        assert_eq!(ad.next_spend_day_at(), 2 * SECONDS_PER_DAY);
+
+        // A new day starts a new count
+        let next_day = noon + SECONDS_PER_DAY;
+        assert_eq!(ad.daily_allowance(930, next_day).unwrap(), 100);
+        ad.record_daily_spend(10, next_day).unwrap();
+        assert_eq!(ad.spend_day, 2);
+        assert_eq!(ad.day_spent, 10);
+
+        ad.daily_cap = 0;
+        assert_eq!(ad.daily_allowance(930, next_day).unwrap(), u64::MAX - 10);
+    }
+
+    #[test]
+    fn test_even_pacing_spreads_unspent_budget() {
+        // Runs from the start of day 0 to the middle of day 3
+        let mut ad = AdAccount {
+            pacing: Pacing::Even,
+            duration: 3 * SECONDS_PER_DAY + SECONDS_PER_DAY / 2,
+            ..AdAccount::default()
+        };
+        assert_eq!(ad.daily_limit(1_000, 0).unwrap(), 250);
+
+        // The day's share is fixed by what was unspent when it began
+        ad.record_daily_spend(250, 100).unwrap();
+        assert_eq!(ad.daily_limit(750, 200).unwrap(), 250);
+        assert_eq!(ad.daily_allowance(750, 200).unwrap(), 0);
+
+        // An underspent day raises the shares of the days after it
+        assert_eq!(ad.daily_limit(750, 2 * SECONDS_PER_DAY).unwrap(), 375);
+        assert_eq!(ad.daily_limit(751, 2 * SECONDS_PER_DAY).unwrap(), 376);
+
+        // A cap below the share still applies
+        ad.daily_cap = 300;
+        assert_eq!(ad.daily_limit(750, 2 * SECONDS_PER_DAY).unwrap(), 300);
+    }
+
+    #[test]
+    fn test_user_rewards_space() {
+        assert_eq!(serialized_len(&UserRewards::default()), UserRewards::SPACE);
+    }
+
+    #[test]
+    fn test_bps_of() {
+        assert_eq!(bps_of(1_000, 0), 0);
+        assert_eq!(bps_of(1_000, 2_500), 250);
+        assert_eq!(bps_of(999, 1), 0);
+        assert_eq!(bps_of(u64::MAX, MAX_BPS), u64::MAX);
+    }
+
+    #[test]
+    fn test_user_profile_space() {
+        let profile = UserProfile {
+            encrypted_data: vec![0; MAX_PROFILE_DATA_SIZE],
+            ..UserProfile::default()
+        };
+        assert_eq!(serialized_len(&profile), UserProfile::SPACE);
+    }
+
+    #[test]
+    fn test_match_request_space() {
+        let request = MatchRequest {
+            candidate_ads: vec![Pubkey::default(); MAX_MATCH_CANDIDATES],
+            ..MatchRequest::default()
+        };
+        assert_eq!(serialized_len(&request), MatchRequest::SPACE);
+    }
+
+    #[test]
+    fn test_matched_ads_account_space() {
+        let encrypted_match = EncryptedMatch::default();
+        assert_eq!(serialized_len(&encrypted_match), EncryptedMatch::SPACE);
+
+        let matched_ads = MatchedAdsAccount {
+            scores_uri: "s".repeat(MAX_CIPHERTEXT_URI_LENGTH),
+            matches: vec![encrypted_match; MAX_MATCHED_ADS],
+            ..MatchedAdsAccount::default()
+        };
+        assert_eq!(serialized_len(&matched_ads), MatchedAdsAccount::SPACE);
+    }
+
+    // Fields are only ever appended before `reserved`, so each size is the
+    // account's first layout plus the fields appended since
+    #[test]
+    fn test_accounts_grow_by_appended_fields() {
+        // event_sequence, cpm_rate, cpc_rate, user_reward_bps,
+        // protocol_fee_bps, total_fees_collected, pending_authority, is_paused
+        assert_eq!(StateAccount::SPACE, 169 + 8 + 8 + 8 + 2 + 2 + 8 + 32 + 1);
+        // The traits bound (1000) was cut to one envelope; then has_vault,
+        // vault_bump, pending_review, campaign, daily_cap, pacing, spend_day,
+        // day_spent, start_at, refunded, mint, deposited
+        let ad_base = 2139 - 1000 + MAX_ENCRYPTED_TRAITS_SIZE;
+        assert_eq!(
+            AdAccount::SPACE,
+            ad_base + 1 + 1 + 1 + 32 + 8 + 1 + 8 + 8 + 8 + 8 + 32 + 8
+        );
+        // The plain email (4 + 100) was replaced by email_commitment; then
+        // open_ad_count, email_verified_at, reputation_updated_at,
+        // campaign_count, open_campaign_count
+        assert_eq!(AdvertiserAccount::SPACE, 258 - 104 + 32 + 8 + 8 + 8 + 8 + 8);
+        // Each encrypted score (4 + 512) was replaced by score_hash; then
+        // scores_uri
+        assert_eq!(
+            MatchedAdsAccount::SPACE,
+            4589 - MAX_MATCHED_ADS * (4 + 512 - 32) + 4 + MAX_CIPHERTEXT_URI_LENGTH
+        );
+        assert_eq!(FheKeyRegistry::SPACE, 319);
+        // The profile bound (1000) was cut to one envelope
+        assert_eq!(UserProfile::SPACE, 1095 - 1000 + MAX_PROFILE_DATA_SIZE);
+        assert_eq!(MatchRequest::SPACE, 381);
+
+        // stake_cooldown; reputation_half_life, review_reputation,
+        // trusted_reputation, limited_budget_cap, reputation_spend_unit and
+        // the three penalties
+        assert_eq!(
+            ProtocolConfig::SPACE,
+            101 + 8 + 8 + 2 + 2 + 8 + 8 + 2 + 2 + 2
+        );
+        // mint
+        assert_eq!(UserRewards::SPACE, 113 + 32);
+        // The traits bound (1000) was cut to one envelope; then mint,
+        // start_at
+        assert_eq!(
+            Campaign::SPACE,
+            1153 - 1000 + MAX_ENCRYPTED_TRAITS_SIZE + 32 + 8
+        );
+    }
+}
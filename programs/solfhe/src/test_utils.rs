@@ -0,0 +1,134 @@
+//! Helpers for calling instruction handlers directly in unit tests.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::SUCCESS;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::program_stubs::{self, SyscallStubs};
+use anchor_spl::token::spl_token;
+
+/// Unix timestamp reported by `Clock::get` in tests.
+pub const TEST_TIMESTAMP: i64 = 1_700_000_000;
+
+struct TestSyscalls;
+
+impl SyscallStubs for TestSyscalls {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: TEST_TIMESTAMP,
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+}
+
+/// Installs syscall stubs so handlers can read the clock. CPIs succeed
+/// without moving funds.
+pub fn install_syscalls() {
+    program_stubs::set_syscall_stubs(Box::new(TestSyscalls));
+}
+
+/// Account data for an existing program account: discriminator plus `account`.
+pub fn account_data<T: AccountSerialize>(account: &T, space: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + space);
+    account.try_serialize(&mut data).unwrap();
+    data.resize(8 + space, 0);
+    data
+}
+
+/// Reads back an account after `Accounts::exit` has written it.
+pub fn read_account<T: AccountDeserialize>(info: &AccountInfo) -> T {
+    T::try_deserialize(&mut &info.data.borrow()[..]).unwrap()
+}
+
+/// Owned backing storage for an `AccountInfo`, so fixtures can be built in a
+/// helper and borrowed by the test.
+pub struct MockAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+impl MockAccount {
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self {
+            key,
+            owner,
+            lamports: 1_000_000_000,
+            data,
+            is_signer: false,
+            is_writable: true,
+            executable: false,
+        }
+    }
+
+    /// A program account that already holds `account`.
+    pub fn program_account<T: AccountSerialize>(key: Pubkey, account: &T, space: usize) -> Self {
+        Self::new(key, crate::ID, account_data(account, space))
+    }
+
+    /// A zeroed program account, as allocated for an `init` constraint.
+    pub fn init(key: Pubkey, space: usize) -> Self {
+        Self::new(key, crate::ID, vec![0; 8 + space])
+    }
+
+    /// A wallet that signs the transaction.
+    pub fn signer(key: Pubkey) -> Self {
+        Self {
+            is_signer: true,
+            ..Self::new(key, anchor_lang::system_program::ID, vec![])
+        }
+    }
+
+    /// An executable program account.
+    pub fn program(id: Pubkey) -> Self {
+        Self {
+            is_writable: false,
+            executable: true,
+            ..Self::new(id, Pubkey::default(), vec![])
+        }
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+
+    /// An initialized SPL token account holding `amount` of `mint`.
+    pub fn token_account(key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) -> Self {
+        let account = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(account, &mut data).unwrap();
+        Self::new(key, spl_token::ID, data)
+    }
+
+    /// An initialized SPL mint with 6 decimals.
+    pub fn mint(key: Pubkey) -> Self {
+        let mint = spl_token::state::Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint::pack(mint, &mut data).unwrap();
+        Self::new(key, spl_token::ID, data)
+    }
+}
@@ -0,0 +1,255 @@
+use crate::error::ErrorCode;
+use crate::state::MAX_NAME_LENGTH;
+use anchor_lang::prelude::*;
+use unicode_normalization::is_nfc;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Longest advertiser name as displayed, in extended grapheme clusters.
+/// `MAX_NAME_LENGTH` separately bounds the stored UTF-8 bytes.
+pub const MAX_NAME_GRAPHEMES: usize = 32;
+
+/// Longest email address: the 256-octet SMTP path of RFC 5321 without the
+/// angle brackets.
+pub const MAX_EMAIL_LENGTH: usize = 254;
+pub const MAX_EMAIL_LOCAL_LENGTH: usize = 64;
+pub const MAX_DOMAIN_LABEL_LENGTH: usize = 63;
+
+/// Checks an advertiser name before it is stored.
+///
+/// The name must be non-empty, already in NFC form (so visually identical
+/// names are byte-identical), free of control, invisible and bidirectional
+/// override characters, without leading, trailing or repeated spaces, and
+/// within both the byte and the grapheme limit.
+pub fn validate_name(name: &str) -> Result<()> {
+    require!(!name.is_empty(), ErrorCode::InvalidAdvertiserName);
+    require!(name.len() <= MAX_NAME_LENGTH, ErrorCode::NameTooLong);
+    require!(
+        name.chars().all(is_allowed_name_char),
+        ErrorCode::DisallowedNameCharacter
+    );
+    require!(
+        !name.starts_with(' ') && !name.ends_with(' ') && !name.contains("  "),
+        ErrorCode::DisallowedNameCharacter
+    );
+    require!(is_nfc(name), ErrorCode::NameNotNormalized);
+    require!(
+        name.graphemes(true).count() <= MAX_NAME_GRAPHEMES,
+        ErrorCode::NameTooLong
+    );
+    Ok(())
+}
+
+/// Checks the syntax of an email address and returns its canonical form,
+/// the one committed to by `AdvertiserAccount::email_commitment`.
+///
+/// Accepts the RFC 5321 `Dot-string "@" Domain` form: an unquoted ASCII local
+/// part and a hostname of at least two labels. Quoted local parts, address
+/// literals and internationalized addresses are rejected. The domain is
+/// case-insensitive and lowercased; the local part is kept as given.
+pub fn normalize_email(email: &str) -> Result<String> {
+    require!(email.len() <= MAX_EMAIL_LENGTH, ErrorCode::EmailTooLong);
+
+    let (local, domain) = email.split_once('@').ok_or(ErrorCode::InvalidEmailSyntax)?;
+    require!(
+        local.len() <= MAX_EMAIL_LOCAL_LENGTH,
+        ErrorCode::EmailTooLong
+    );
+    require!(
+        is_dot_string(local) && is_hostname(domain),
+        ErrorCode::InvalidEmailSyntax
+    );
+
+    Ok(format!("{}@{}", local, domain.to_ascii_lowercase()))
+}
+
+fn is_allowed_name_char(c: char) -> bool {
+    !c.is_control()
+        && (c == ' ' || !c.is_whitespace())
+        && !matches!(
+            c,
+            // Zero-width characters and the byte order mark
+            '\u{200B}'..='\u{200F}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{FEFF}'
+                // Bidirectional embeddings, overrides and isolates
+                | '\u{202A}'..='\u{202E}'
+                | '\u{2066}'..='\u{2069}'
+                // Interlinear annotations and object replacement
+                | '\u{FFF9}'..='\u{FFFC}'
+        )
+}
+
+// RFC 5321 `Dot-string`: atoms of `atext` separated by single dots.
+fn is_dot_string(local: &str) -> bool {
+    local.split('.').all(|atom| {
+        !atom.is_empty()
+            && atom
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-/=?^_`{|}~".contains(&b))
+    })
+}
+
+// RFC 5321 `Domain` restricted to hostnames: LDH labels of up to 63 octets,
+// at least two of them, and a top-level label that is not all digits.
+fn is_hostname(domain: &str) -> bool {
+    let labels: Vec<&str> = domain.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            (1..=MAX_DOMAIN_LABEL_LENGTH).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+        && !labels[labels.len() - 1].bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn name_error(name: &str) -> Error {
+        validate_name(name).unwrap_err()
+    }
+
+    fn email_error(email: &str) -> Error {
+        normalize_email(email).unwrap_err()
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("Acme Ads").is_ok());
+        assert!(validate_name("Café Müller & Söhne").is_ok());
+        assert!(validate_name("広告株式会社").is_ok());
+
+        assert_eq!(name_error(""), ErrorCode::InvalidAdvertiserName.into());
+        assert_eq!(
+            name_error("Cafe\u{301}"),
+            ErrorCode::NameNotNormalized.into()
+        );
+        for name in [
+            "Acme\nAds",
+            "Acme\u{202E}sdA",
+            "Acme\u{200B}Ads",
+            "Acme\u{00A0}Ads",
+            " Acme",
+            "Acme ",
+            "Acme  Ads",
+        ] {
+            assert_eq!(
+                name_error(name),
+                ErrorCode::DisallowedNameCharacter.into(),
+                "{name:?}"
+            );
+        }
+        assert_eq!(
+            name_error(&"a".repeat(MAX_NAME_GRAPHEMES + 1)),
+            ErrorCode::NameTooLong.into()
+        );
+        assert_eq!(
+            name_error(&"é".repeat(MAX_NAME_LENGTH)),
+            ErrorCode::NameTooLong.into()
+        );
+    }
+
+    #[test]
+    fn test_normalize_email() {
+        assert_eq!(
+            normalize_email("Ads.Team+solfhe@Example.COM").unwrap(),
+            "Ads.Team+solfhe@example.com"
+        );
+        assert!(normalize_email("a@b.co").is_ok());
+        assert!(normalize_email("x@mail.example-one.org").is_ok());
+
+        for email in [
+            "",
+            "ads",
+            "@example.com",
+            "ads@",
+            "ads@example",
+            "ads@@example.com",
+            "a@b@example.com",
+            ".ads@example.com",
+            "ads.@example.com",
+            "ad..s@example.com",
+            "ads@-example.com",
+            "ads@example-.com",
+            "ads@example..com",
+            "ads@example.123",
+            "ads@[127.0.0.1]",
+            "\"ads\"@example.com",
+            "ad s@example.com",
+            "adé@example.com",
+        ] {
+            assert_eq!(
+                email_error(email),
+                ErrorCode::InvalidEmailSyntax.into(),
+                "{email:?}"
+            );
+        }
+
+        let local = "a".repeat(MAX_EMAIL_LOCAL_LENGTH + 1);
+        assert_eq!(
+            email_error(&format!("{local}@example.com")),
+            ErrorCode::EmailTooLong.into()
+        );
+        let label = "a".repeat(MAX_DOMAIN_LABEL_LENGTH + 1);
+        assert_eq!(
+            email_error(&format!("ads@{label}.com")),
+            ErrorCode::InvalidEmailSyntax.into()
+        );
+        let domain = vec!["a".repeat(MAX_DOMAIN_LABEL_LENGTH); 4].join(".");
+        assert_eq!(
+            email_error(&format!("ads@{domain}")),
+            ErrorCode::EmailTooLong.into()
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_printable_names_are_accepted(
+            name in "[A-Za-z0-9àéöüßçñ&'.,-]{1,8}( [A-Za-z0-9àéöüßçñ&'.,-]{1,8}){0,2}"
+        ) {
+            prop_assert!(validate_name(&name).is_ok());
+        }
+
+        #[test]
+        fn prop_control_characters_are_rejected(
+            prefix in "[a-z]{0,10}",
+            control in prop::char::ranges(vec!['\0'..='\u{1F}', '\u{7F}'..='\u{9F}'].into()),
+            suffix in "[a-z]{0,10}",
+        ) {
+            let name = format!("{prefix}{control}{suffix}");
+            prop_assert_eq!(name_error(&name), ErrorCode::DisallowedNameCharacter.into());
+        }
+
+        #[test]
+        fn prop_accepted_names_fit_the_account(name in any::<String>()) {
+            if validate_name(&name).is_ok() {
+                prop_assert!(name.len() <= MAX_NAME_LENGTH);
+                prop_assert!(name.graphemes(true).count() <= MAX_NAME_GRAPHEMES);
+                prop_assert!(is_nfc(&name));
+            }
+        }
+
+        #[test]
+        fn prop_generated_emails_are_normalized(
+            local in "[A-Za-z0-9_+-]{1,20}(\\.[A-Za-z0-9_+-]{1,20}){0,2}",
+            domain in "[A-Za-z0-9]([A-Za-z0-9-]{0,20}[A-Za-z0-9])?(\\.[A-Za-z0-9]{1,10}){0,2}\\.[A-Za-z]{2,6}",
+        ) {
+            let normalized = normalize_email(&format!("{local}@{domain}")).unwrap();
+            prop_assert_eq!(&normalized, &format!("{local}@{}", domain.to_ascii_lowercase()));
+            prop_assert_eq!(normalize_email(&normalized).unwrap(), normalized);
+        }
+
+        #[test]
+        fn prop_accepted_emails_are_bounded(email in any::<String>()) {
+            if let Ok(normalized) = normalize_email(&email) {
+                prop_assert!(normalized.is_ascii());
+                prop_assert!(normalized.len() <= MAX_EMAIL_LENGTH);
+            }
+        }
+    }
+}